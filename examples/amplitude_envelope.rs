@@ -17,11 +17,11 @@ fn envelope(max: f32, min: f32, cur: u32, lim: u32) -> f32 {
 }
 
 fn main() {
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
     prepare_default_pcm!(pcm);
     let (max, min, size) = (0.5, 0.0, SAMPLING_FREQUENCY * 4);
     
-    let buf: Vec<_> = (0..size).into_iter()
+    let buf: Vec<_> = (0..size)
         .map(|i| envelope(max, min, i, size - 1))
         .enumerate()
         .map(|(i, a)| a * (2.0 * PI * FREQUENCY as f32 * i as f32 / SAMPLING_FREQUENCY as f32).sin())
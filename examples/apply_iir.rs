@@ -39,7 +39,7 @@ fn main(){
         }
     }
 
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
 
     let hw_params = HwParams::any(&pcm).unwrap(); 
     hw_params.set_channels(1).unwrap();     
@@ -16,7 +16,7 @@ fn chirp(max: f32, min: f32, cur: u32, lim: u32) -> f32 {
 }
 
 fn main() {
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
     prepare_default_pcm!(pcm);
     let (max, min, amp, size) = (
         2500.0, 
@@ -25,7 +25,7 @@ fn main() {
         SAMPLING_FREQUENCY / 5
     );
     
-    let buf: Vec<_> = (0..size).into_iter()
+    let buf: Vec<_> = (0..size)
         .map(|i| chirp(max, min, i, size - 1))
         .map(|freq| amp * (2.0 * PI * freq / SAMPLING_FREQUENCY as f32).sin())
         .collect();
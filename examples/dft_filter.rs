@@ -4,88 +4,42 @@ extern crate examples;
 use std::ffi::CString;
 use alsa::{ Direction, ValueOr };
 use alsa::pcm::{ Access, Format, HwParams, PCM }; 
-use examples::{ fft, fir_lpf, hann, ifft, read_wave_mono16 };
+use examples::{ Complex, Istft, KaiserDesign, Response, Stft, complex_mul, fft, read_wave_mono16 };
 
 const SAMPLE_FILE: &str = "examples/resources/sine_500hz_3500hz.wav";
 const FRAME_LEN: usize = 128;
 const DFT_LEN: usize = 256; 
 
-fn build_input(source: &Vec<f32>, i: usize, l: usize, n: usize) -> Vec<(f32, f32)> {
-    let (mut frame, mut zeros): (Vec<_>, _) = (
-        (0..l).into_iter().map(|j| source[l * i  + j]).collect(),
-        vec![0.0; n-l]
-    );
-    frame.append(&mut zeros);
-    fft(frame)
-}
-
-fn build_filter(source: &Vec<f32>, l: usize, n: usize) -> Vec<(f32, f32)> {
-    let filter: Vec<_> = (0..n).into_iter().map(|i| match i {
+fn build_filter(source: &[f32], l: usize, n: usize) -> Vec<Complex<f32>> {
+    let filter: Vec<_> = (0..n).map(|i| match i {
         i if i <= l => source[i],
         _ => 0.0
     }).collect(); fft(filter)
 }
 
-fn apply_filter(input: Vec<(f32, f32)>, filter: &Vec<(f32, f32)>) -> Vec<f32> {
-    let output: Vec<_> = input.into_iter().zip(filter.into_iter()).map(|((x_real, x_image), &(b_real, b_image))| (
-        x_real * b_real - x_image * b_image,
-        x_image * b_real + x_real * b_image
-    )).collect(); 
-    ifft(output).into_iter().map(|(r, _)| r).collect()  
-}
-
 fn main() {
-    let (
-        sample_freq, 
-        data, 
-        edge_freq,
-        delta
-    ) = { 
+    let (sample_freq, data) = { 
         let wave = read_wave_mono16(SAMPLE_FILE); 
-        let (rate, data) = (wave.format.sample_rate, wave.data);
-
-        (
-            rate, 
-            data, 
-            1000.0 / rate as f32,
-            1000.0 / rate as f32
-        )
-    };
-
-    let num = match (3.1 / delta + 0.5) as i32 - 1 {
-        num if num % 2 == 1 => num + 1,
-        num => num
+        (wave.format.sample_rate, wave.data)
     };
 
-    let (
-        fir_filter, 
-        frame_num,
-        data_length
-    ) = ( 
-        fir_lpf(edge_freq, num as isize, hann((num + 1) as usize)),
-        data.len() / FRAME_LEN,
-        data.len()
+    // Passes 1 kHz and rejects 60 dB from 2 kHz, which fits in one frame at 8 kHz.
+    let (design, stft) = ( 
+        KaiserDesign::new(Response::LowPass(1000.0), Response::LowPass(2000.0), sample_freq, 60.0).design(),
+        Stft::new(vec![1.0; FRAME_LEN], FRAME_LEN).with_fft_len(DFT_LEN)
     );
+    assert!(design.taps.len() <= DFT_LEN - FRAME_LEN + 1, "filter too long for the frame");
+    println!("{} taps, {:.3} dB ripple, {:.1} dB attenuation", design.taps.len(), design.ripple, design.attenuation);
 
-    let filter = build_filter(&fir_filter, num as usize, DFT_LEN); 
-
-    let buf = (0..frame_num).into_iter().map(|i| {
-        let input = build_input(&data, i, FRAME_LEN, DFT_LEN);
-        apply_filter(input, &filter)
-    }).enumerate().fold(vec![0.0f32; data_length], |mut acc, (i, v)| {
-       for (j, &x) in v.iter().enumerate() {
-           let offset = i * FRAME_LEN + j;
+    let filter = build_filter(&design.taps, design.taps.len() - 1, DFT_LEN); 
+    let istft = Istft::from_stft(&stft).with_synthesis_window(vec![1.0; DFT_LEN]);
 
-           if offset < data_length {
-               acc[offset] += x;
-           }
-       }
-       acc
-    });
+    let frames: Vec<_> = stft.frames(&data).map(|input| complex_mul(input, &filter)).collect();
+    let buf = istft.synthesize(&frames, data.len());
 
     println!("{:?}", buf);
 
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
 
     let hw_params = HwParams::any(&pcm).unwrap(); 
     hw_params.set_channels(1).unwrap();     
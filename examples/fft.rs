@@ -9,7 +9,7 @@ fn main() {
     let mut data = wave.data.clone(); 
     data.truncate(64);
 
-    for (idx, &(r, i)) in fft(data).iter().enumerate() {
-        println!("X({}) = {:.32} + {:.32}i", idx, r, i);
+    for (idx, c) in fft(data).iter().enumerate() {
+        println!("X({}) = {:.32} + {:.32}i", idx, c.re, c.im);
     }
 }
@@ -28,7 +28,7 @@ fn main() {
         392.00, 440.00, 493.88, 523.25
     ];
 
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
     prepare_default_pcm!(pcm);
 
     println!("PCM status: {:?}, {:?}", pcm.state(), pcm.hw_params_current().unwrap());
@@ -11,7 +11,7 @@ const SAMPLING_FREQUENCY: u32 = 44100;
 const GAIN: f32 = 0.1;
 
 fn main() {
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
     prepare_default_pcm!(pcm);
     let (size, amps, freqs) = (
         SAMPLING_FREQUENCY * 4,
@@ -19,7 +19,7 @@ fn main() {
         vec![440, 880, 1320, 1760, 2200]
     );
     
-    let buf: Vec<_> = (0..size).into_iter()
+    let buf: Vec<_> = (0..size)
         .map(|i| amps.iter().zip(freqs.iter())
             .fold(0.0, |acc, (a, &f)| acc + a * (2.0 * PI * f as f32 * i as f32 / size as f32).sin()))
         .map(|x| x * GAIN)
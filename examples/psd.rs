@@ -0,0 +1,15 @@
+extern crate examples;
+use examples::{ Symmetry, Welch, read_wave_mono16 };
+use examples::window::hann;
+
+const SAMPLE_FILE: &str = "examples/resources/sine_500hz_3500hz.wav";
+const SEGMENT_LEN: usize = 1024;
+
+fn main() {
+    let wave = read_wave_mono16(SAMPLE_FILE);
+    let psd = Welch::new(hann(SEGMENT_LEN, Symmetry::Periodic), SEGMENT_LEN / 2).estimate_wave(&wave);
+
+    for ((freq, density), power) in psd.frequencies.iter().zip(psd.density_dbfs()).zip(psd.power_dbfs()) {
+        println!("{:8.1} Hz  {:7.1} dBFS/Hz  {:7.1} dBFS", freq, density, power);
+    }
+}
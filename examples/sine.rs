@@ -16,7 +16,7 @@ const SAMPLING_FREQUENCY: u32 = 44100;
 const AMPLITUDE: f32 = 1.0;
 
 fn main() {
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
     prepare_default_pcm!(pcm);
 
     println!("PCM status: {:?}, {:?}", pcm.state(), pcm.hw_params_current().unwrap());
@@ -0,0 +1,23 @@
+extern crate examples;
+use examples::{ Colormap, FrequencyScale, Renderer, Spectrogram, Stft, Symmetry, read_wave_mono16 };
+use examples::window::hann;
+
+const SAMPLE_FILE: &str = "examples/resources/sine_500hz_3500hz.wav";
+const FRAME_LEN: usize = 512;
+
+fn main() {
+    let wave = read_wave_mono16(SAMPLE_FILE);
+    let stft = Stft::new(hann(FRAME_LEN, Symmetry::Periodic), FRAME_LEN / 4);
+    let spectrogram = Spectrogram::from_wave(&wave, &stft);
+
+    Renderer::new(256)
+        .with_range_db(90.0)
+        .with_colormap(Colormap::Viridis)
+        .render(&spectrogram)
+        .save("spectrogram.png").unwrap();
+
+    Renderer::new(256)
+        .with_scale(FrequencyScale::Log(50.0))
+        .render(&spectrogram)
+        .save("spectrogram_log.pgm").unwrap();
+}
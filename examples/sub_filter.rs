@@ -6,7 +6,7 @@ use alsa::{ Direction, ValueOr };
 use alsa::pcm::{ Access, Format, HwParams, PCM }; 
 use examples::{ iir_lpf, read_wave_mono16 };
 
-const SAMPLE_FILE: &'static str = "examples/resources/pulse_train.wav";
+const SAMPLE_FILE: &str = "examples/resources/pulse_train.wav";
 const INPUT_DELAYERS: usize = 2;
 const FILTER_DELAYERS: usize = 2; 
 
@@ -24,7 +24,7 @@ fn main() {
         (wave.format.sample_rate, wave.data)
     };
 
-    let buf: Vec<_> = (0..data.len()).into_iter()
+    let buf: Vec<_> = (0..data.len())
         .map(|i| {
             let base = -5.0 * i as f32 / data.len() as f32;
             10000.0 * base.exp() 
@@ -34,19 +34,19 @@ fn main() {
             let (input, filter) = 
                 iir_lpf(x / sample_frequency as f32, (2.0_f32).sqrt()); 
 
-            let res = (0..(FILTER_DELAYERS + 1)).into_iter().fold(0.0, |acc, j| match i as isize - j as isize {
+            let res = (0..(FILTER_DELAYERS + 1)).fold(0.0, |acc, j| match i as isize - j as isize {
                  offset if offset >= 0 => acc + get_weight(input, j) * data[offset as usize],
                  _ =>  acc
             });
 
-            (1..(INPUT_DELAYERS + 1)).into_iter().fold(res, |acc, j| match i as isize - j as isize {
+            (1..(INPUT_DELAYERS + 1)).fold(res, |acc, j| match i as isize - j as isize {
                  offset if offset >= 0 => acc - get_weight(filter, j) * data[offset as usize],
                  _ =>  acc
             })
         })
         .collect();
 
-    let pcm = PCM::open(&*CString::new("default").unwrap(), Direction::Playback, false).unwrap();
+    let pcm = PCM::open(&CString::new("default").unwrap(), Direction::Playback, false).unwrap();
 
     let hw_params = HwParams::any(&pcm).unwrap(); 
 
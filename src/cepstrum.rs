@@ -0,0 +1,203 @@
+use complex::Complex;
+use float::Float;
+use pitch::parabolic_offset;
+use { fft, fft_complex, ifft, unwrap_phase };
+
+/// Spectrum of `src` zero padded to a power of two.
+fn spectrum<T: Float>(src: &[T]) -> Vec<Complex<T>> {
+    let mut v = src.to_vec();
+    v.resize(src.len().next_power_of_two(), T::zero());
+    fft(v)
+}
+
+fn log_magnitude<T: Float>(c: Complex<T>) -> T {
+    c.norm().max(T::from_f64(1e-30)).ln()
+}
+
+/// Real cepstrum `ifft(ln |fft(src)|)` of `src` zero padded to a power of two. Index
+/// `n` is quefrency `n` samples, with negative quefrencies mirrored into the second half.
+pub fn real_cepstrum<T: Float>(src: &[T]) -> Vec<T> {
+    let log = spectrum(src).into_iter().map(|c| Complex::new(log_magnitude(c), T::zero())).collect();
+    ifft(log).into_iter().map(|c| c.re).collect()
+}
+
+/// Power cepstrum `|ifft(ln |fft(src)|^2)|^2`, laid out like `real_cepstrum`.
+pub fn power_cepstrum<T: Float>(src: &[T]) -> Vec<T> {
+    let two = T::from_f64(2.0);
+    let log = spectrum(src).into_iter().map(|c| Complex::new(two * log_magnitude(c), T::zero())).collect();
+    ifft(log).into_iter().map(|c| c.norm_sqr()).collect()
+}
+
+/// Complex cepstrum `ifft(ln |X| + j arg X)` with the phase unwrapped and its linear part
+/// removed, after MATLAB's `cceps`. Returns the cepstrum and the removed delay in
+/// samples, which `inverse_complex_cepstrum` puts back. `src` should sum to a positive
+/// value, or its sign is lost.
+pub fn complex_cepstrum<T: Float>(src: &[T]) -> (Vec<T>, isize) {
+    let x = spectrum(src);
+    let (n, half) = (x.len(), x.len() / 2);
+    let phase = unwrap_phase(&x[..half + 1].iter().map(Complex::arg).collect::<Vec<_>>());
+    let linear = (phase[half] / T::PI).round();
+
+    let log = (0..n).map(|k| {
+        let bin = if k > half { n - k } else { k };
+        let arg = phase[bin] - T::PI * linear * T::from_usize(bin) / T::from_usize(half);
+        let c = Complex::new(log_magnitude(x[bin]), arg);
+        if k > half { c.conj() } else { c }
+    }).collect();
+    (ifft(log).into_iter().map(|c| c.re).collect(), -linear.to_f64() as isize)
+}
+
+/// Signal of a complex cepstrum from `complex_cepstrum`, delayed again by `delay` samples.
+pub fn inverse_complex_cepstrum<T: Float>(cepstrum: &[T], delay: isize) -> Vec<T> {
+    let n = cepstrum.len();
+    let step = -T::from_f64(2.0) * T::PI * T::from_f64(delay as f64) / T::from_usize(n);
+    let log = fft_complex(cepstrum.iter().map(|&c| Complex::new(c, T::zero())).collect());
+    let x = log.into_iter().enumerate().map(|(k, c)| {
+        Complex::from_polar(c.re.exp(), c.im + step * T::from_usize(k))
+    }).collect();
+    ifft(x).into_iter().map(|c| c.re).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lifter {
+    /// Keeps the slowly varying spectral envelope.
+    Low,
+    /// Keeps the fine structure, such as the excitation's harmonics.
+    High
+}
+
+/// Keeps the quefrencies below `cutoff` samples (`Lifter::Low`) or from `cutoff` up
+/// (`Lifter::High`) of a cepstrum laid out like `real_cepstrum`, on both sides of zero.
+pub fn lifter<T: Float>(cepstrum: &[T], cutoff: usize, kind: Lifter) -> Vec<T> {
+    let n = cepstrum.len();
+    cepstrum.iter().enumerate().map(|(i, &c)| match (kind, i.min(n - i) < cutoff) {
+        (Lifter::Low, true) | (Lifter::High, false) => c,
+        _ => T::zero()
+    }).collect()
+}
+
+/// Smoothed magnitude spectrum of `src` in dB for bins `0..=len / 2` of its padded FFT,
+/// from the real cepstrum low liftered at `cutoff`.
+pub fn spectral_envelope<T: Float>(src: &[T], cutoff: usize) -> Vec<T> {
+    let low = lifter(&real_cepstrum(src), cutoff, Lifter::Low);
+    let scale = T::from_f64(20.0) / T::from_f64(10.0).ln();
+    let half = low.len() / 2;
+    fft(low).into_iter().take(half + 1).map(|c| c.re * scale).collect()
+}
+
+/// Fundamental of a windowed frame from its highest real cepstrum peak between the
+/// quefrencies of `max_frequency` and `min_frequency`. Returns the frequency in Hz and
+/// the peak's height, which stays near zero for unvoiced frames, or `None` when the
+/// frame is too short to hold that quefrency range.
+pub fn cepstral_pitch<T: Float>(src: &[T], sample_rate: u32, min_frequency: T, max_frequency: T) -> Option<(T, T)> {
+    let c = real_cepstrum(src);
+    let rate = T::from_f64(sample_rate as f64);
+    let lo = ((rate / max_frequency).floor().to_f64() as usize).max(2);
+    let hi = ((rate / min_frequency).ceil().to_f64() as usize).min((c.len() / 2).saturating_sub(1));
+    if lo >= hi {
+        return None;
+    }
+
+    let best = (lo..hi + 1).fold(lo, |best, q| if c[q] > c[best] { q } else { best });
+    let quefrency = T::from_usize(best) + parabolic_offset(c[best - 1], c[best], c[best + 1]);
+    Some((rate / quefrency, c[best]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Lifter, cepstral_pitch, complex_cepstrum, inverse_complex_cepstrum, lifter, power_cepstrum,
+        real_cepstrum, spectral_envelope
+    };
+    use synth::noise;
+    use window::{ Symmetry, hann };
+    use { apply_fir, read_wave_mono16 };
+
+    fn pulse_train() -> (Vec<f64>, u32) {
+        let wave = read_wave_mono16("examples/resources/pulse_train.wav");
+        (wave.data.iter().map(|&x| x as f64).collect(), wave.format.sample_rate)
+    }
+
+    fn windowed(src: &[f64]) -> Vec<f64> {
+        src.iter().zip(hann::<f64>(src.len(), Symmetry::Periodic)).map(|(x, w)| x * w).collect()
+    }
+
+    #[test]
+    fn test_single_zero() {
+        // ln(1 - a / z) = -sum a^n / (n z^n), so the complex cepstrum of a delayed single
+        // zero is -a^n / n for n > 0 and the real cepstrum its even half.
+        let a = 0.5f64;
+        let mut src = vec![0.0; 256];
+        src[2] = 1.0;
+        src[3] = -a;
+
+        let (complex, delay) = complex_cepstrum(&src);
+        let (real, power) = (real_cepstrum(&src), power_cepstrum(&src));
+        assert_eq!(delay, 2);
+        assert!(complex[0].abs() < 1e-12 && real[0].abs() < 1e-12);
+        for n in 1..20 {
+            let expected = -a.powi(n as i32) / n as f64;
+            assert!((complex[n] - expected).abs() < 1e-12, "{}: {} != {}", n, complex[n], expected);
+            assert!(complex[256 - n].abs() < 1e-12);
+            assert!((real[n] - expected / 2.0).abs() < 1e-12 && (real[256 - n] - expected / 2.0).abs() < 1e-12);
+            assert!((power[n] - expected * expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut src: Vec<f64> = noise(0.3, 300, 7);
+        src[40] += 2.0;
+        let (ceps, delay) = complex_cepstrum(&src);
+        assert_eq!(ceps.len(), 512);
+        assert!(delay > 0);
+
+        let res = inverse_complex_cepstrum(&ceps, delay);
+        for (i, x) in res.iter().enumerate() {
+            let expected = src.get(i).cloned().unwrap_or(0.0);
+            assert!((x - expected).abs() < 1e-9, "{}: {} != {}", i, x, expected);
+        }
+    }
+
+    #[test]
+    fn test_pulse_train_quefrency() {
+        // The file's pulses repeat every 88.2 samples, 500 Hz at 44.1 kHz.
+        let (src, rate) = pulse_train();
+        let frame = windowed(&src[20000..20000 + 2048]);
+        let high = lifter(&real_cepstrum(&frame), 20, Lifter::High);
+        let peak = (0..1024).fold(0, |best, q| if high[q] > high[best] { q } else { best });
+        assert!(peak == 88 || peak == 89, "{}", peak);
+
+        let (frequency, height) = cepstral_pitch(&frame, rate, 60.0, 2000.0).expect("pitch");
+        assert!((frequency - 500.0).abs() < 2.0, "{}", frequency);
+        let (_, unvoiced) = cepstral_pitch(&windowed(&noise(0.5, 2048, 3)), rate, 60.0, 2000.0).expect("pitch");
+        assert!(height > 5.0 * unvoiced, "{} vs {}", height, unvoiced);
+    }
+
+    #[test]
+    fn test_pitch_of_short_frames() {
+        // 2000 Hz is 22 samples of quefrency, beyond the 15 a 32 point cepstrum holds.
+        assert_eq!(cepstral_pitch(&[0.5; 32], 44100, 60.0, 2000.0), None);
+        for len in 0..3 {
+            assert_eq!(cepstral_pitch(&vec![0.5; len], 44100, 60.0, 2000.0), None);
+        }
+    }
+
+    #[test]
+    fn test_envelope() {
+        // A 100 sample pulse train through a resonance at 0.15 of the sample rate: the
+        // envelope peaks at the resonance rather than at the nearest harmonic.
+        let resonance: Vec<f64> = (0..400)
+            .map(|n| 0.97f64.powi(n) * (0.3 * ::std::f64::consts::PI * n as f64).cos()).collect();
+        let pulses: Vec<f64> = (0..2048).map(|n| if n % 100 == 0 { 1.0 } else { 0.0 }).collect();
+        let envelope = spectral_envelope(&windowed(&apply_fir(&resonance, &pulses)), 30);
+        assert_eq!(envelope.len(), 1025);
+
+        let peak = (0..envelope.len()).fold(0, |best, k| if envelope[k] > envelope[best] { k } else { best });
+        assert!((peak as f64 - 0.3 * 1024.0).abs() < 10.0, "{}", peak);
+        assert!(envelope[peak] - envelope[0] > 15.0 && envelope[peak] - envelope[1000] > 15.0);
+        for k in 1..envelope.len() {
+            assert!((envelope[k] - envelope[k - 1]).abs() < 1.0, "{}", k);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt;
+use std::ops::Range;
+use pitch::parabolic_offset;
+use stft::Stft;
+use window::{ Symmetry, hann };
+use Wave;
+
+pub const PITCH_CLASSES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// Krumhansl-Kessler key profiles, starting from the tonic.
+pub const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+pub const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Major,
+    Minor
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Key {
+    /// Pitch class of the tonic, 0 for C.
+    pub tonic: usize,
+    pub mode: Mode,
+    /// Pearson correlation between the chroma and the key profile.
+    pub correlation: f32
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = match self.mode {
+            Mode::Major => "major",
+            Mode::Minor => "minor"
+        };
+        write!(f, "{} {}", PITCH_CLASSES[self.tonic], mode)
+    }
+}
+
+fn correlation(a: &[f32], b: &[f32]) -> f32 {
+    let mean = |x: &[f32]| x.iter().sum::<f32>() / x.len() as f32;
+    let (ma, mb) = (mean(a), mean(b));
+    let cov: f32 = a.iter().zip(b.iter()).map(|(x, y)| (x - ma) * (y - mb)).sum();
+    let var = |x: &[f32], m: f32| x.iter().map(|v| (v - m).powi(2)).sum::<f32>();
+    match (var(a, ma) * var(b, mb)).sqrt() {
+        d if d > 0.0 => cov / d,
+        _ => 0.0
+    }
+}
+
+/// Key whose rotated profile correlates best with the summed chroma.
+pub fn estimate_key(chroma: &[[f32; 12]]) -> Key {
+    let total: Vec<f32> = (0..12).map(|c| chroma.iter().map(|frame| frame[c]).sum()).collect();
+    let candidates = [(Mode::Major, MAJOR_PROFILE), (Mode::Minor, MINOR_PROFILE)];
+
+    candidates.iter().flat_map(|&(mode, profile)| (0..12).map(move |tonic| (mode, profile, tonic)))
+        .map(|(mode, profile, tonic)| {
+            let rotated: Vec<_> = (0..12).map(|c| profile[(c + 12 - tonic) % 12]).collect();
+            Key { tonic, mode, correlation: correlation(&total, &rotated) }
+        })
+        .fold(None, |best: Option<Key>, key| match best {
+            Some(b) if b.correlation >= key.correlation => Some(b),
+            _ => Some(key)
+        }).unwrap()
+}
+
+#[derive(Clone, Debug)]
+pub struct Chromagram {
+    /// Offset of the recording's tuning from A440 equal temperament in semitones.
+    pub tuning: f32,
+    /// Per-frame pitch class energies, scaled so the strongest class is 1.
+    pub frames: Vec<[f32; 12]>
+}
+
+impl Chromagram {
+    pub fn key(&self) -> Key {
+        estimate_key(&self.frames)
+    }
+}
+
+/// Maps STFT power between `min_frequency` and `max_frequency` onto the nearest
+/// semitone's pitch class, after shifting the reference pitch by the tuning estimated
+/// from the spectral peaks. A range holding no bins gives zero tuning and silent frames.
+#[derive(Clone, Debug)]
+pub struct Chroma {
+    pub stft: Stft<f32>,
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    pub reference: f32
+}
+
+impl Chroma {
+    pub fn new(fft_len: usize) -> Self {
+        Chroma {
+            stft: Stft::new(hann(fft_len, Symmetry::Periodic), fft_len / 4),
+            min_frequency: 60.0,
+            max_frequency: 5000.0,
+            reference: 440.0
+        }
+    }
+
+    fn power_frames(&self, src: &[f32]) -> Vec<Vec<f32>> {
+        let bins = self.stft.fft_len / 2 + 1;
+        self.stft.frames(src).map(|f| f.iter().take(bins).map(|c| c.norm_sqr()).collect()).collect()
+    }
+
+    /// Bins between `min_frequency` and `max_frequency` that have a neighbour on either
+    /// side, empty when no bin qualifies.
+    fn bin_range(&self, sample_rate: u32) -> Range<usize> {
+        let width = sample_rate as f32 / self.stft.fft_len as f32;
+        let lo = (self.min_frequency / width).ceil().max(1.0) as usize;
+        let hi = ((self.max_frequency / width).floor() as usize).min((self.stft.fft_len / 2).saturating_sub(1));
+        lo.min(hi + 1)..hi + 1
+    }
+
+    /// Semitones above `reference` in equal temperament, so 0 is A4.
+    fn semitones(&self, freq: f32) -> f32 {
+        12.0 * (freq / self.reference).log2()
+    }
+
+    fn tuning(&self, frames: &[Vec<f32>], sample_rate: u32) -> f32 {
+        let range = self.bin_range(sample_rate);
+        let width = sample_rate as f32 / self.stft.fft_len as f32;
+        let (mut sin, mut cos) = (0.0, 0.0);
+
+        for power in frames {
+            let peak = power[range.clone()].iter().cloned().fold(0.0, f32::max);
+            for k in range.clone() {
+                if power[k] > 0.01 * peak && power[k] > power[k - 1] && power[k] >= power[k + 1] {
+                    let db = |x: f32| x.max(1e-20).ln();
+                    let freq = (k as f32 + parabolic_offset(db(power[k - 1]), db(power[k]), db(power[k + 1]))) * width;
+                    let deviation = self.semitones(freq) - self.semitones(freq).round();
+                    let angle = 2.0 * ::std::f32::consts::PI * deviation;
+                    sin += power[k].sqrt() * angle.sin();
+                    cos += power[k].sqrt() * angle.cos();
+                }
+            }
+        }
+        match sin != 0.0 || cos != 0.0 {
+            true => f32::atan2(sin, cos) / (2.0 * ::std::f32::consts::PI),
+            false => 0.0
+        }
+    }
+
+    pub fn compute(&self, src: &[f32], sample_rate: u32) -> Chromagram {
+        let frames = self.power_frames(src);
+        let tuning = self.tuning(&frames, sample_rate);
+        let range = self.bin_range(sample_rate);
+        let width = sample_rate as f32 / self.stft.fft_len as f32;
+        let classes: Vec<usize> = range.clone()
+            .map(|k| ((self.semitones(k as f32 * width) - tuning).round() as i64 + 9).rem_euclid(12) as usize).collect();
+
+        let frames = frames.iter().map(|power| {
+            let mut chroma = [0.0; 12];
+            for (k, &class) in range.clone().zip(classes.iter()) {
+                chroma[class] += power[k];
+            }
+            let peak = chroma.iter().cloned().fold(0.0, f32::max);
+            if peak > 0.0 {
+                chroma.iter_mut().for_each(|c| *c /= peak);
+            }
+            chroma
+        }).collect();
+
+        Chromagram { tuning, frames }
+    }
+
+    pub fn compute_wave(&self, wave: &Wave) -> Chromagram {
+        self.compute(&wave.data, wave.format.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Chroma, Mode, estimate_key };
+    use synth::harmonics;
+
+    const RATE: u32 = 22050;
+    const OCTAVE: [f32; 8] = [261.63, 293.66, 329.63, 349.23, 392.00, 440.00, 493.88, 523.25];
+
+    /// The scale of `octave.rs`, each note a quarter second with a few harmonics.
+    fn scale(freqs: &[f32], detune_cents: f32) -> Vec<f32> {
+        let shift = 2f32.powf(detune_cents / 1200.0);
+        freqs.iter().flat_map(|&f| harmonics(f * shift, &[0.5, 0.2, 0.1], RATE, RATE as usize / 4)).collect()
+    }
+
+    #[test]
+    fn test_c_major_scale() {
+        let chroma = Chroma::new(4096).compute(&scale(&OCTAVE, 0.0), RATE);
+        assert!(chroma.tuning.abs() < 0.05, "{}", chroma.tuning);
+
+        let key = chroma.key();
+        assert_eq!((key.tonic, key.mode), (0, Mode::Major));
+        assert_eq!(format!("{}", key), "C major");
+
+        let total: Vec<f32> = (0..12).map(|c| chroma.frames.iter().map(|f| f[c]).sum()).collect();
+        for &black in &[1, 3, 6, 8, 10] {
+            assert!(total[black] < 0.2 * total[0], "{:?}", total);
+        }
+    }
+
+    #[test]
+    fn test_detuned_minor_scale() {
+        // A harmonic minor, 30 cents sharp.
+        let a_minor = [220.0, 246.94, 261.63, 293.66, 329.63, 349.23, 415.30, 440.0];
+        let chroma = Chroma::new(4096).compute(&scale(&a_minor, 30.0), RATE);
+        assert!((chroma.tuning - 0.3).abs() < 0.05, "{}", chroma.tuning);
+
+        let key = chroma.key();
+        assert_eq!(format!("{}", key), "A minor");
+        assert!(key.correlation > 0.7);
+    }
+
+    #[test]
+    fn test_empty_range() {
+        let src = scale(&OCTAVE, 0.0);
+        let reversed = Chroma { min_frequency: 1000.0, max_frequency: 500.0, ..Chroma::new(4096) };
+        let above_nyquist = Chroma { min_frequency: 30000.0, max_frequency: 40000.0, ..Chroma::new(4096) };
+        for chroma in &[reversed, above_nyquist] {
+            let res = chroma.compute(&src, RATE);
+            assert_eq!(res.tuning, 0.0);
+            assert_eq!(res.frames.len(), chroma.stft.frame_count(src.len()));
+            assert!(res.frames.iter().all(|f| f.iter().all(|&c| c == 0.0)));
+        }
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut frame = [0.0; 12];
+        for &c in &[7, 11, 2] {
+            frame[c] = 1.0;
+        }
+        frame[7] = 2.0;
+        let key = estimate_key(&[frame]);
+        assert_eq!((key.tonic, key.mode), (7, Mode::Major));
+    }
+}
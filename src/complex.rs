@@ -0,0 +1,180 @@
+use std::fmt;
+use float::Float;
+use std::ops::{ Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign };
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Complex<T> {
+    pub re: T,
+    pub im: T
+}
+
+impl<T> Complex<T> {
+    pub fn new(re: T, im: T) -> Self {
+        Complex { re, im }
+    }
+}
+
+impl<T> From<(T, T)> for Complex<T> {
+    fn from((re, im): (T, T)) -> Self {
+        Complex::new(re, im)
+    }
+}
+
+impl<T> From<Complex<T>> for (T, T) {
+    fn from(c: Complex<T>) -> Self {
+        (c.re, c.im)
+    }
+}
+
+impl<T: fmt::Display + PartialOrd + Default + Neg<Output = T> + Copy> fmt::Display for Complex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.im {
+            im if im < T::default() => write!(f, "{} - {}i", self.re, -im),
+            im => write!(f, "{} + {}i", self.re, im)
+        }
+    }
+}
+
+impl<T: Copy + Add<Output = T>> Add for Complex<T> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl<T: Copy + Sub<Output = T>> Sub for Complex<T> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>> Mul for Complex<T> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re
+        )
+    }
+}
+
+impl<T> Div for Complex<T>
+    where T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let denom = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom
+        )
+    }
+}
+
+impl<T: Copy + Neg<Output = T>> Neg for Complex<T> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+impl<T: Copy + Mul<Output = T>> Mul<T> for Complex<T> {
+    type Output = Self;
+    fn mul(self, rhs: T) -> Self {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl<T: Copy + Div<Output = T>> Div<T> for Complex<T> {
+    type Output = Self;
+    fn div(self, rhs: T) -> Self {
+        Complex::new(self.re / rhs, self.im / rhs)
+    }
+}
+
+macro_rules! __assign_op {
+    ( $( $tr:ident, $f:ident, $op:ident, $of:ident );+ ) => {$(
+        impl<T: Copy> $tr for Complex<T> where Complex<T>: $op<Output = Complex<T>> {
+            fn $f(&mut self, rhs: Self) { *self = (*self).$of(rhs); }
+        }
+    )+}
+}
+
+__assign_op! {
+    AddAssign, add_assign, Add, add;
+    SubAssign, sub_assign, Sub, sub;
+    MulAssign, mul_assign, Mul, mul;
+    DivAssign, div_assign, Div, div
+}
+
+impl<T: Float> Complex<T> {
+    pub fn i() -> Self { Complex::new(T::zero(), T::one()) }
+
+    pub fn conj(&self) -> Self { Complex::new(self.re, -self.im) }
+
+    pub fn norm_sqr(&self) -> T { self.re * self.re + self.im * self.im }
+
+    pub fn norm(&self) -> T { self.re.hypot(self.im) }
+
+    pub fn arg(&self) -> T { self.im.atan2(self.re) }
+
+    pub fn from_polar(r: T, theta: T) -> Self {
+        Complex::new(r * theta.cos(), r * theta.sin())
+    }
+
+    pub fn to_polar(&self) -> (T, T) { (self.norm(), self.arg()) }
+
+    /// Magnitude in decibels, `20 log10 |z|`. Zero maps to negative infinity.
+    pub fn db(&self) -> T { T::from_f64(10.0) * self.norm_sqr().log10() }
+
+    pub fn exp(&self) -> Self { Self::from_polar(self.re.exp(), self.im) }
+
+    pub fn cast<U: Float>(&self) -> Complex<U> {
+        Complex::new(U::from_f64(self.re.to_f64()), U::from_f64(self.im.to_f64()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Complex;
+    use std::f64::consts::PI;
+
+    fn assert_close(a: Complex<f64>, b: Complex<f64>) {
+        assert!((a - b).norm() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let (a, b) = (Complex::new(1.0, 2.0), Complex::new(3.0, -4.0));
+
+        assert_eq!(a + b, Complex::new(4.0, -2.0));
+        assert_eq!(a - b, Complex::new(-2.0, 6.0));
+        assert_eq!(a * b, Complex::new(11.0, 2.0));
+        assert_close(a / b * b, a);
+        assert_eq!(-a, Complex::new(-1.0, -2.0));
+        assert_eq!(a * Complex::<f64>::i(), Complex::new(-2.0, 1.0));
+
+        let mut c = a;
+        c *= b; c -= b; c /= a;
+        assert_close(c, (a * b - b) / a);
+    }
+
+    #[test]
+    fn test_polar() {
+        let c = Complex::<f64>::from_polar(2.0, PI / 3.0);
+
+        assert!((c.norm() - 2.0).abs() < 1e-12);
+        assert!((c.arg() - PI / 3.0).abs() < 1e-12);
+        assert_eq!(c.conj().arg(), -c.arg());
+        assert!((c.norm_sqr() - c.norm().powi(2)).abs() < 1e-12);
+        assert_close(Complex::new(0.0, PI).exp(), Complex::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_db() {
+        assert!((Complex::<f64>::new(10.0, 0.0).db() - 20.0).abs() < 1e-12);
+        assert!((Complex::<f64>::new(0.0, 0.1).db() + 20.0).abs() < 1e-12);
+        assert_eq!(Complex::<f64>::default().db(), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,281 @@
+use complex::Complex;
+use window::{ Symmetry, hann };
+use { fft_complex, ifft };
+use Wave;
+
+/// Constant-Q transform by spectral kernels (Brown and Puckette): bin `k` at
+/// `min_frequency * 2^(k / bins_per_octave)` is the inner product of a frame's FFT with
+/// the sparse FFT of a Hann windowed complex sinusoid `Q` periods long, centred in the
+/// frame. A sinusoid at a bin's frequency reads its amplitude. The kernels are built by
+/// `new` and rebuilt by the `with_` methods, so the public fields are for reading.
+#[derive(Clone, Debug)]
+pub struct ConstantQ {
+    pub sample_rate: u32,
+    pub min_frequency: f32,
+    pub bins_per_octave: usize,
+    pub bins: usize,
+    /// Scales `Q` below its default of one bin spacing per bandwidth, trading frequency
+    /// resolution for time resolution.
+    q_scale: f32,
+    /// Set by `with_hop`, otherwise a quarter of the shortest kernel.
+    hop: Option<usize>,
+    pub fft_len: usize,
+    /// Kernel values of each bin below `threshold` times their peak are dropped.
+    threshold: f32,
+    pub kernels: Vec<Vec<(usize, Complex<f32>)>>
+}
+
+impl ConstantQ {
+    /// Hop of a quarter of the shortest kernel.
+    pub fn new(sample_rate: u32, min_frequency: f32, bins_per_octave: usize, bins: usize) -> Self {
+        assert!(bins > 0, "a constant-Q transform needs at least one bin");
+        assert!(bins_per_octave > 0, "a constant-Q transform needs at least one bin per octave");
+        assert!(min_frequency > 0.0, "lowest bin must lie above DC");
+        ConstantQ {
+            sample_rate, min_frequency, bins_per_octave, bins,
+            q_scale: 1.0, hop: None, fft_len: 0, threshold: 1e-3, kernels: Vec::new()
+        }.build()
+    }
+
+    /// Rebuilds the kernels, and the default hop with them unless `with_hop` set one.
+    pub fn with_q_scale(mut self, q_scale: f32) -> Self {
+        assert!(q_scale > 0.0, "q scale must be positive");
+        self.q_scale = q_scale;
+        self.build()
+    }
+
+    /// Drops kernel values below `threshold` times each kernel's peak, 1e-3 by default.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        assert!((0.0..1.0).contains(&threshold), "threshold must lie in [0, 1)");
+        self.threshold = threshold;
+        self.build()
+    }
+
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        assert!(hop > 0, "hop must be positive");
+        self.hop = Some(hop);
+        self
+    }
+
+    pub fn hop(&self) -> usize {
+        self.hop.unwrap_or_else(|| (self.kernel_len(self.bins - 1) / 4).max(1))
+    }
+
+    pub fn q_scale(&self) -> f32 {
+        self.q_scale
+    }
+
+    pub fn q(&self) -> f32 {
+        self.q_scale / (2f32.powf(1.0 / self.bins_per_octave as f32) - 1.0)
+    }
+
+    pub fn frequency(&self, bin: usize) -> f32 {
+        self.min_frequency * 2f32.powf(bin as f32 / self.bins_per_octave as f32)
+    }
+
+    pub fn frequencies(&self) -> Vec<f32> {
+        (0..self.bins).map(|k| self.frequency(k)).collect()
+    }
+
+    fn kernel_len(&self, bin: usize) -> usize {
+        (self.q() * self.sample_rate as f32 / self.frequency(bin)).ceil() as usize
+    }
+
+    fn build(mut self) -> Self {
+        assert!(self.frequency(self.bins - 1) < self.sample_rate as f32 / 2.0, "highest bin is above Nyquist");
+        self.fft_len = self.kernel_len(0).next_power_of_two();
+        let fft_len = self.fft_len;
+        self.kernels = (0..self.bins).map(|k| {
+            let len = self.kernel_len(k);
+            let (window, offset) = (hann::<f32>(len, Symmetry::Periodic), (fft_len - len) / 2);
+            let gain = 2.0 / window.iter().sum::<f32>();
+            let step = 2.0 * ::std::f32::consts::PI * self.frequency(k) / self.sample_rate as f32;
+
+            let mut temporal = vec![Complex::default(); fft_len];
+            for (n, w) in window.into_iter().enumerate() {
+                temporal[offset + n] = Complex::from_polar(w * gain, step * (n as f32 - len as f32 / 2.0));
+            }
+            let spectral: Vec<_> = fft_complex(temporal).into_iter().map(|c| c.conj() / fft_len as f32).collect();
+            let peak = spectral.iter().map(|c| c.norm()).fold(0.0, f32::max);
+            spectral.into_iter().enumerate().filter(|&(_, c)| c.norm() >= self.threshold * peak).collect()
+        }).collect();
+        self
+    }
+
+    /// Number of frames, centred every `hop` samples from the first sample.
+    pub fn frame_count(&self, len: usize) -> usize {
+        len.div_ceil(self.hop())
+    }
+
+    pub fn frame_time(&self, index: usize) -> f32 {
+        (index * self.hop()) as f32 / self.sample_rate as f32
+    }
+
+    /// Constant-Q coefficients of one frame's `fft_len` point spectrum.
+    pub fn transform_frame(&self, spectrum: &[Complex<f32>]) -> Vec<Complex<f32>> {
+        self.kernels.iter().map(|kernel| kernel.iter()
+            .fold(Complex::default(), |acc, &(j, k)| acc + spectrum[j] * k)).collect()
+    }
+
+    pub fn transform(&self, src: &[f32]) -> Vec<Vec<Complex<f32>>> {
+        (0..self.frame_count(src.len())).map(|m| {
+            let start = (m * self.hop()) as isize - (self.fft_len / 2) as isize;
+            let frame = (0..self.fft_len as isize).map(|n| match start + n {
+                i if i >= 0 && (i as usize) < src.len() => Complex::new(src[i as usize], 0.0),
+                _ => Complex::default()
+            }).collect();
+            self.transform_frame(&fft_complex(frame))
+        }).collect()
+    }
+
+    pub fn transform_wave(&self, wave: &Wave) -> Vec<Vec<Complex<f32>>> {
+        assert_eq!(wave.format.sample_rate, self.sample_rate, "wave and transform sample rates differ");
+        self.transform(&wave.data)
+    }
+
+    /// Approximate inverse of `transform` for a signal of `len` samples. Each frame is
+    /// resynthesized from the kernels and overlap-added, with every FFT bin divided by
+    /// the kernels' summed power there, so content inside the analysed range comes
+    /// back close to unchanged and everything outside it is dropped.
+    pub fn inverse(&self, frames: &[Vec<Complex<f32>>], len: usize) -> Vec<f32> {
+        let mut power = vec![0.0; self.fft_len];
+        for &(j, k) in self.kernels.iter().flatten() {
+            power[j] += k.norm_sqr();
+        }
+        let peak = power.iter().cloned().fold(0.0, f32::max);
+        let scale: Vec<f32> = power.into_iter().map(|p| match p {
+            // Doubled for the negative frequencies the kernels leave out.
+            p if p > 1e-3 * peak => 2.0 * self.hop() as f32 / (self.fft_len as f32 * p),
+            _ => 0.0
+        }).collect();
+
+        let mut dest = vec![0.0; len];
+        for (m, coeffs) in frames.iter().enumerate() {
+            let mut spectrum = vec![Complex::default(); self.fft_len];
+            for (kernel, &c) in self.kernels.iter().zip(coeffs.iter()) {
+                for &(j, k) in kernel {
+                    spectrum[j] += c * k.conj() * scale[j];
+                }
+            }
+            let start = (m * self.hop()) as isize - (self.fft_len / 2) as isize;
+            for (n, x) in ifft(spectrum).into_iter().enumerate() {
+                match start + n as isize {
+                    i if i >= 0 && (i as usize) < len => dest[i as usize] += x.re,
+                    _ => {}
+                }
+            }
+        }
+        dest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConstantQ;
+    use synth::{ mix, sine };
+
+    const RATE: u32 = 22050;
+    const OCTAVE: [f32; 8] = [261.63, 293.66, 329.63, 349.23, 392.00, 440.00, 493.88, 523.25];
+
+    fn loudest(frame: &[f32]) -> usize {
+        (0..frame.len()).fold(0, |best, k| if frame[k] > frame[best] { k } else { best })
+    }
+
+    fn magnitudes(cqt: &ConstantQ, src: &[f32]) -> Vec<Vec<f32>> {
+        cqt.transform(src).iter().map(|f| f.iter().map(|c| c.norm()).collect()).collect()
+    }
+
+    #[test]
+    fn test_geometry() {
+        let cqt = ConstantQ::new(RATE, 55.0, 12, 60);
+        assert!((cqt.frequency(12) - 110.0).abs() < 1e-3);
+        assert!((cqt.frequency(36) - 440.0).abs() < 1e-2);
+        assert!((cqt.q() - 16.817).abs() < 1e-2);
+        assert_eq!(cqt.fft_len, 8192);
+        assert_eq!(cqt.kernels.len(), 60);
+        assert!(cqt.kernels.iter().all(|k| k.len() < cqt.fft_len / 8));
+
+        let wide = cqt.clone().with_q_scale(0.5);
+        assert!((wide.q() - cqt.q() / 2.0).abs() < 1e-4);
+        assert_eq!(wide.fft_len, 4096);
+        assert!((wide.hop() as f32 - cqt.hop() as f32 / 2.0).abs() <= 1.0, "{} vs {}", wide.hop(), cqt.hop());
+        assert_eq!(cqt.clone().with_hop(100).with_q_scale(0.5).hop(), 100);
+
+        let sparse = cqt.clone().with_threshold(0.1);
+        let entries = |c: &ConstantQ| c.kernels.iter().map(|k| k.len()).sum::<usize>();
+        assert!(entries(&sparse) < entries(&cqt) / 2, "{} vs {}", entries(&sparse), entries(&cqt));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one bin")]
+    fn test_without_bins() {
+        ConstantQ::new(RATE, 55.0, 12, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one bin per octave")]
+    fn test_without_bins_per_octave() {
+        ConstantQ::new(RATE, 55.0, 0, 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "above DC")]
+    fn test_at_dc() {
+        ConstantQ::new(RATE, 0.0, 12, 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "q scale must be positive")]
+    fn test_without_q_scale() {
+        ConstantQ::new(RATE, 55.0, 12, 12).with_q_scale(0.0);
+    }
+
+    #[test]
+    fn test_tone_amplitude() {
+        let cqt = ConstantQ::new(RATE, 55.0, 12, 60).with_hop(1024);
+        let frames = magnitudes(&cqt, &sine(440.0, 0.5, RATE, RATE as usize));
+        let middle = &frames[frames.len() / 2];
+        assert_eq!(loudest(middle), 36);
+        assert!((middle[36] - 0.5).abs() < 0.01, "{}", middle[36]);
+        assert!(middle[33] < 0.01 && middle[39] < 0.01, "{:?}", &middle[33..40]);
+    }
+
+    #[test]
+    fn test_octave_notes() {
+        // C4 is bin 24 above C2; the scale of `octave.rs` in semitones above it.
+        let cqt = ConstantQ::new(RATE, 65.406, 12, 48).with_hop(512);
+        let note_len = RATE as usize / 2;
+        let src: Vec<f32> = OCTAVE.iter().flat_map(|&f| sine(f, 0.5, RATE, note_len)).collect();
+        let frames = magnitudes(&cqt, &src);
+
+        for (i, &semitone) in [0, 2, 4, 5, 7, 9, 11, 12].iter().enumerate() {
+            let centre = (i * note_len + note_len / 2) / cqt.hop();
+            assert_eq!(loudest(&frames[centre]), 24 + semitone, "note {}", i);
+        }
+    }
+
+    #[test]
+    fn test_semitone_resolution() {
+        // A2 and B flat 2 are a semitone apart, bins 24 and 26 at 24 bins per octave: both
+        // read close to their amplitude with a dip at the quarter tone between them.
+        let cqt = ConstantQ::new(RATE, 55.0, 24, 96).with_hop(2048);
+        let src = mix(&[sine(110.0, 0.5, RATE, RATE as usize * 2), sine(116.54, 0.5, RATE, RATE as usize * 2)]);
+        let frames = magnitudes(&cqt, &src);
+        let middle = &frames[frames.len() / 2];
+        assert!(middle[24] > 0.4 && middle[26] > 0.4 && middle[25] < 0.3, "{:?}", &middle[22..29]);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let cqt = ConstantQ::new(RATE, 100.0, 24, 120);
+        let len = RATE as usize;
+        let src = mix(&[sine(220.0, 0.3, RATE, len), sine(1234.0, 0.2, RATE, len), sine(3000.0, 0.1, RATE, len)]);
+        let res = cqt.inverse(&cqt.transform(&src), len);
+
+        let range = cqt.fft_len..len - cqt.fft_len;
+        let error: f32 = range.clone().map(|i| (res[i] - src[i]).powi(2)).sum();
+        let energy: f32 = range.map(|i| src[i].powi(2)).sum();
+        let snr = 10.0 * (energy / error).log10();
+        assert!(snr > 40.0, "{} dB", snr);
+    }
+}
@@ -0,0 +1,145 @@
+use complex::Complex;
+use float::Float;
+use { fft_complex, ifft };
+
+// exp(-j pi step n^2), reducing n^2 modulo the chirp period in f64 so long inputs keep
+// their phase accuracy even in f32.
+fn chirp<T: Float>(n: usize, step: f64) -> Complex<T> {
+    let arg = (::std::f64::consts::PI * step * (n as f64).powi(2)) % (2.0 * ::std::f64::consts::PI);
+    Complex::from_polar(T::one(), T::from_f64(-arg))
+}
+
+pub fn czt_frequencies<T: Float>(start: T, end: T, points: usize) -> Vec<T> {
+    let step = match points {
+        0 | 1 => T::zero(),
+        _ => (end - start) / T::from_usize(points - 1)
+    };
+    (0..points).map(|k| start + step * T::from_usize(k)).collect()
+}
+
+/// Spectrum of `src` at `points` frequencies spaced evenly from `start` to `end` Hz
+/// (inclusive), computed with Bluestein's algorithm on top of `fft`.
+pub fn czt<T: Float>(src: &[T], start: T, end: T, points: usize, sample_rate: u32) -> Vec<Complex<T>> {
+    let src: Vec<_> = src.iter().map(|&x| Complex::new(x, T::zero())).collect();
+    czt_complex(&src, start, end, points, sample_rate)
+}
+
+pub fn czt_complex<T: Float>(src: &[Complex<T>], start: T, end: T, points: usize, sample_rate: u32)
+    -> Vec<Complex<T>> {
+    if src.is_empty() || points == 0 {
+        return vec![Complex::default(); points];
+    }
+
+    let rate = sample_rate as f64;
+    let (offset, step) = (
+        start.to_f64() / rate,
+        match points {
+            1 => 0.0,
+            _ => (end.to_f64() - start.to_f64()) / rate / (points - 1) as f64
+        }
+    );
+    let (n, m) = (src.len(), points);
+    let len = (n + m - 1).next_power_of_two();
+
+    let mut input: Vec<_> = src.iter().enumerate().map(|(i, &x)| {
+        let shift = -2.0 * ::std::f64::consts::PI * ((offset * i as f64) % 1.0);
+        x * Complex::from_polar(T::one(), T::from_f64(shift)) * chirp(i, step)
+    }).collect();
+    input.resize(len, Complex::default());
+
+    let mut kernel = vec![Complex::default(); len];
+    for i in 0..m.max(n) {
+        let c = chirp::<T>(i, step).conj();
+        if i < m { kernel[i] = c; }
+        if i > 0 && i < n { kernel[len - i] = c; }
+    }
+
+    let spectrum: Vec<_> = fft_complex(input).into_iter().zip(fft_complex(kernel))
+        .map(|(x, h)| x * h).collect();
+
+    ifft(spectrum).into_iter().take(m).enumerate().map(|(k, y)| y * chirp(k, step)).collect()
+}
+
+/// DFT of any length: radix-2 `fft` for powers of two, Bluestein otherwise.
+pub fn fft_any<T: Float>(src: Vec<Complex<T>>) -> Vec<Complex<T>> {
+    match src.len() {
+        0 => Vec::new(),
+        len if len.is_power_of_two() => fft_complex(src),
+        len => czt_complex(&src, T::zero(), T::from_usize(len - 1), len, len as u32)
+    }
+}
+
+pub fn ifft_any<T: Float>(src: Vec<Complex<T>>) -> Vec<Complex<T>> {
+    let n = T::from_usize(src.len());
+    fft_any(src.into_iter().map(|c| c.conj()).collect()).into_iter().map(|c| c.conj() / n).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ czt, czt_frequencies, fft_any, ifft_any };
+    use complex::Complex;
+    use dft::dft_complex;
+    use goertzel::goertzel_generalized;
+    use synth::{ mix, sine };
+    use fft;
+
+    const RATE: u32 = 8000;
+
+    #[test]
+    fn test_matches_dtft() {
+        let src: Vec<f64> = (0..300).map(|i| (i as f64 * 0.21).sin() * (1.0 + (i as f64 * 0.05).cos())).collect();
+        let (start, end, points) = (420.0, 610.0, 77);
+        let res = czt(&src, start, end, points, RATE);
+
+        for (&f, c) in czt_frequencies(start, end, points).iter().zip(res.iter()) {
+            let expected = goertzel_generalized(&src, f * src.len() as f64 / RATE as f64);
+            assert!((*c - expected).norm() < 1e-8, "{} Hz: {:?} != {:?}", f, c, expected);
+        }
+    }
+
+    #[test]
+    fn test_full_circle_matches_fft() {
+        let src: Vec<f64> = (0..64).map(|i| ((i * 7 % 11) as f64) - 5.0).collect();
+        let res = czt(&src, 0.0, RATE as f64 * 63.0 / 64.0, 64, RATE);
+
+        for (x, y) in res.iter().zip(fft(src).iter()) {
+            assert!((*x - *y).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_resolves_close_tones() {
+        let len = 2048;
+        let src = mix(&[sine(500.0f32, 1.0, RATE, len), sine(512.0, 1.0, RATE, len), sine(3500.0, 1.0, RATE, len)]);
+        let (freqs, res) = (czt_frequencies(480.0f32, 532.0, 105), czt(&src, 480.0, 532.0, 105, RATE));
+        let mags: Vec<_> = res.iter().map(|c| c.norm()).collect();
+
+        let peaks: Vec<_> = (1..mags.len() - 1)
+            .filter(|&i| mags[i] > mags[i - 1] && mags[i] > mags[i + 1] && mags[i] > 0.3 * len as f32 / 2.0)
+            .map(|i| freqs[i]).collect();
+
+        assert_eq!(peaks.len(), 2);
+        assert!((peaks[0] - 500.0).abs() <= 0.5 && (peaks[1] - 512.0).abs() <= 0.5);
+    }
+
+    #[test]
+    fn test_single_point() {
+        let src = sine(1000.0f64, 1.0, RATE, 80);
+        let res = czt(&src, 1000.0, 1000.0, 1, RATE);
+
+        assert_eq!(res.len(), 1);
+        assert!((res[0].norm() - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fft_any() {
+        for &len in &[3, 12, 40, 64] {
+            let src: Vec<_> = (0..len).map(|i| Complex::new((i as f64 * 0.9).sin(), (i as f64 * 0.4).cos())).collect();
+            let (res, expected) = (fft_any(src.clone()), dft_complex(&src));
+
+            assert!(res.iter().zip(expected.iter()).all(|(x, y)| (*x - *y).norm() < 1e-9));
+            assert!(ifft_any(res).iter().zip(src.iter()).all(|(x, y)| (*x - *y).norm() < 1e-9));
+        }
+        assert!(fft_any::<f64>(Vec::new()).is_empty() && ifft_any::<f64>(Vec::new()).is_empty());
+    }
+}
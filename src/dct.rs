@@ -0,0 +1,224 @@
+use complex::Complex;
+use czt::{ fft_any, ifft_any };
+use float::Float;
+use window::{ Symmetry, kaiser };
+
+fn ortho_scale<T: Float>(k: usize, len: usize) -> T {
+    match k {
+        0 => (T::one() / T::from_usize(len)).sqrt(),
+        _ => (T::from_f64(2.0) / T::from_usize(len)).sqrt()
+    }
+}
+
+/// Orthonormal DCT-II, `X(k) = s(k) sum x(n) cos(pi k (n + 1/2) / N)`.
+pub fn dct2<T: Float>(src: &[T]) -> Vec<T> {
+    let len = src.len();
+    let reordered: Vec<_> = (0..len).map(|n| match n {
+        n if n < len.div_ceil(2) => src[2 * n],
+        n => src[2 * (len - 1 - n) + 1]
+    }).map(|x| Complex::new(x, T::zero())).collect();
+
+    fft_any(reordered).into_iter().enumerate().map(|(k, v)| {
+        let shift = Complex::from_polar(T::one(), -T::PI * T::from_usize(k) / T::from_usize(2 * len));
+        (v * shift).re * ortho_scale(k, len)
+    }).collect()
+}
+
+/// Orthonormal DCT-III, the inverse of `dct2`.
+pub fn dct3<T: Float>(src: &[T]) -> Vec<T> {
+    let len = src.len();
+    let unscaled: Vec<_> = src.iter().enumerate().map(|(k, &x)| x / ortho_scale(k, len)).collect();
+    let spectrum: Vec<_> = (0..len).map(|k| {
+        let shift = Complex::from_polar(T::one(), T::PI * T::from_usize(k) / T::from_usize(2 * len));
+        let mirror = match k {
+            0 => T::zero(),
+            k => unscaled[len - k]
+        };
+        shift * Complex::new(unscaled[k], -mirror)
+    }).collect();
+
+    let v = ifft_any(spectrum);
+    (0..len).map(|n| match n % 2 {
+        0 => v[n / 2].re,
+        _ => v[len - 1 - n / 2].re
+    }).collect()
+}
+
+/// Unnormalized DCT-IV, `X(k) = sum x(n) cos(pi (n + 1/2) (k + 1/2) / N)`, for even `N`.
+/// Applying it twice scales the input by `N / 2`.
+pub fn dct4<T: Float>(src: &[T]) -> Vec<T> {
+    let (len, half) = (src.len(), src.len() / 2);
+    assert!(len % 2 == 0, "dct4 length must be even, got {}", len);
+
+    let folded: Vec<_> = (0..half).map(|m| {
+        let arg = -T::PI * (T::from_usize(m) + T::from_f64(0.25)) / T::from_usize(len);
+        let twiddle = Complex::from_polar(T::one(), arg);
+        Complex::new(src[2 * m], src[len - 1 - 2 * m]) * twiddle
+    }).collect();
+
+    let mut res = vec![T::zero(); len];
+    for (k, c) in fft_any(folded).into_iter().enumerate() {
+        let y = c * Complex::from_polar(T::one(), -T::PI * T::from_usize(k) / T::from_usize(len));
+        res[2 * k] = y.re;
+        res[len - 1 - 2 * k] = -y.im;
+    }
+    res
+}
+
+/// MDCT of a `2M` sample frame into `M` coefficients.
+pub fn mdct<T: Float>(frame: &[T]) -> Vec<T> {
+    let (len, q) = (frame.len(), frame.len() / 4);
+    assert!(len % 4 == 0, "mdct frame length must be a multiple of 4, got {}", len);
+
+    // (a, b, c, d) -> (-c_r - d, a - b_r)
+    let folded: Vec<_> = (0..len / 2).map(|n| match n {
+        n if n < q => -frame[3 * q - 1 - n] - frame[3 * q + n],
+        n => frame[n - q] - frame[3 * q - 1 - n]
+    }).collect();
+    dct4(&folded)
+}
+
+/// IMDCT of `M` coefficients into a `2M` sample frame, scaled so that overlap-adding
+/// frames windowed on both sides by a Princen-Bradley window reconstructs the input.
+pub fn imdct<T: Float>(coeffs: &[T]) -> Vec<T> {
+    let (m, half) = (coeffs.len(), coeffs.len() / 2);
+    let v: Vec<_> = dct4(coeffs).into_iter().map(|x| x * T::from_f64(2.0) / T::from_usize(m)).collect();
+
+    // (v1, v2) -> (v2, -v2_r, -v1_r, -v1)
+    (0..2 * m).map(|n| match n / half {
+        0 => v[half + n],
+        1 => -v[m + half - 1 - n],
+        2 => -v[half - 1 - (n - m)],
+        _ => -v[n - 3 * half]
+    }).collect()
+}
+
+pub fn sine_window<T: Float>(len: usize) -> Vec<T> {
+    (0..len).map(|n| (T::PI * (T::from_usize(n) + T::from_f64(0.5)) / T::from_usize(len)).sin()).collect()
+}
+
+/// Kaiser-Bessel-derived window of even length `len` with shape parameter `alpha`.
+pub fn kbd_window<T: Float>(len: usize, alpha: T) -> Vec<T> {
+    let half = len / 2;
+    let kaiser = kaiser(half + 1, T::PI * alpha, Symmetry::Symmetric);
+
+    let total: T = kaiser.iter().cloned().sum();
+    let cumulative: Vec<T> = kaiser.iter().scan(T::zero(), |acc, &x| { *acc += x; Some(*acc) }).collect();
+    (0..len).map(|n| match n {
+        n if n < half => (cumulative[n] / total).sqrt(),
+        n => (cumulative[len - 1 - n] / total).sqrt()
+    }).collect()
+}
+
+/// Splits `src` into 50% overlapping windowed MDCT frames. The signal is padded with
+/// `window.len() / 2` zeros on both sides so every sample is covered by two frames.
+pub fn mdct_analyze<T: Float>(src: &[T], window: &[T]) -> Vec<Vec<T>> {
+    let hop = window.len() / 2;
+    let blocks = src.len().div_ceil(hop) + 1;
+    let mut padded = vec![T::zero(); hop];
+    padded.extend_from_slice(src);
+    padded.resize((blocks + 1) * hop, T::zero());
+
+    (0..blocks).map(|b| {
+        let frame: Vec<_> = padded[b * hop..b * hop + 2 * hop].iter().zip(window.iter())
+            .map(|(&x, &w)| x * w).collect();
+        mdct(&frame)
+    }).collect()
+}
+
+/// Inverse of `mdct_analyze`: windowed IMDCT with overlap-add, trimmed to `len` samples.
+pub fn mdct_synthesize<T: Float>(frames: &[Vec<T>], window: &[T], len: usize) -> Vec<T> {
+    let hop = window.len() / 2;
+    let mut out = vec![T::zero(); (frames.len() + 1) * hop];
+    for (b, coeffs) in frames.iter().enumerate() {
+        for (n, (x, &w)) in imdct(coeffs).into_iter().zip(window.iter()).enumerate() {
+            out[b * hop + n] += x * w;
+        }
+    }
+    out.into_iter().skip(hop).take(len).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ dct2, dct3, dct4, imdct, kbd_window, mdct, mdct_analyze, mdct_synthesize, sine_window };
+    use std::f64::consts::PI;
+
+    fn signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| (i as f64 * 0.37).sin() + 0.5 * (i as f64 * 2.1).cos() + 0.01 * i as f64).collect()
+    }
+
+    fn assert_close(a: &[f64], b: &[f64], tolerance: f64) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < tolerance, "{} != {}", x, y);
+        }
+    }
+
+    #[test]
+    fn test_dct2_dct3() {
+        for &len in &[1, 5, 8, 40, 64] {
+            let src = signal(len);
+            let expected: Vec<_> = (0..len).map(|k| {
+                let scale = if k == 0 { (1.0 / len as f64).sqrt() } else { (2.0 / len as f64).sqrt() };
+                scale * src.iter().enumerate()
+                    .map(|(n, x)| x * (PI * k as f64 * (n as f64 + 0.5) / len as f64).cos()).sum::<f64>()
+            }).collect();
+
+            assert_close(&dct2(&src), &expected, 1e-9);
+            assert_close(&dct3(&dct2(&src)), &src, 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_dct4() {
+        for &len in &[2, 8, 24, 32] {
+            let src = signal(len);
+            let expected: Vec<_> = (0..len).map(|k| src.iter().enumerate()
+                .map(|(n, x)| x * (PI / len as f64 * (n as f64 + 0.5) * (k as f64 + 0.5)).cos()).sum())
+                .collect();
+
+            assert_close(&dct4(&src), &expected, 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_mdct_definition() {
+        let (frame, m) = (signal(32), 16);
+        let expected: Vec<_> = (0..m).map(|k| frame.iter().enumerate().map(|(n, x)| {
+            x * (PI / m as f64 * (n as f64 + 0.5 + m as f64 / 2.0) * (k as f64 + 0.5)).cos()
+        }).sum()).collect();
+        let inverse: Vec<_> = (0..2 * m).map(|n| expected.iter().enumerate().map(|(k, x): (usize, &f64)| {
+            x * (PI / m as f64 * (n as f64 + 0.5 + m as f64 / 2.0) * (k as f64 + 0.5)).cos()
+        }).sum::<f64>() * 2.0 / m as f64).collect();
+
+        assert_close(&mdct(&frame), &expected, 1e-9);
+        assert_close(&imdct(&expected), &inverse, 1e-9);
+    }
+
+    #[test]
+    fn test_empty() {
+        let empty: [f64; 0] = [];
+        assert!(dct2(&empty).is_empty() && dct3(&empty).is_empty() && dct4(&empty).is_empty());
+        assert!(mdct(&empty).is_empty() && imdct(&empty).is_empty());
+    }
+
+    #[test]
+    fn test_princen_bradley() {
+        for window in &[sine_window::<f64>(64), kbd_window(64, 4.0)] {
+            for n in 0..32 {
+                assert!((window[n].powi(2) + window[n + 32].powi(2) - 1.0).abs() < 1e-12);
+                assert!((window[n] - window[63 - n]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tdac_reconstruction() {
+        let src = signal(1000);
+        for window in &[sine_window::<f64>(256), kbd_window(256, 4.0), kbd_window(48, 6.0)] {
+            let frames = mdct_analyze(&src, window);
+            assert_eq!(frames[0].len(), window.len() / 2);
+            assert_close(&mdct_synthesize(&frames, window, src.len()), &src, 1e-9);
+        }
+    }
+}
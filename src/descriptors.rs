@@ -0,0 +1,157 @@
+use stft::Stft;
+use window::{ Symmetry, hann };
+use Wave;
+
+/// Per-frame descriptors, each series aligned with `times` (frame centres in seconds).
+/// Spectral descriptors are in Hz where they have units and are zero for silent frames.
+#[derive(Clone, Debug, Default)]
+pub struct Descriptors {
+    pub times: Vec<f32>,
+    /// Magnitude weighted mean frequency.
+    pub centroid: Vec<f32>,
+    /// Magnitude weighted standard deviation around the centroid.
+    pub bandwidth: Vec<f32>,
+    /// Frequency below which `rolloff` of the frame's energy lies.
+    pub rolloff: Vec<f32>,
+    /// Geometric over arithmetic mean of the power spectrum, 0 for tones and near 1 for noise.
+    pub flatness: Vec<f32>,
+    /// Euclidean distance between consecutive magnitude spectra.
+    pub flux: Vec<f32>,
+    /// Peak over mean magnitude.
+    pub crest: Vec<f32>,
+    /// Sign changes per sample.
+    pub zero_crossing_rate: Vec<f32>,
+    pub rms: Vec<f32>
+}
+
+#[derive(Clone, Debug)]
+pub struct DescriptorExtractor {
+    pub stft: Stft<f32>,
+    pub rolloff: f32
+}
+
+impl DescriptorExtractor {
+    /// Periodic Hann frames of `frame_len` samples every `hop` samples, 85% rolloff.
+    pub fn new(frame_len: usize, hop: usize) -> Self {
+        DescriptorExtractor { stft: Stft::new(hann(frame_len, Symmetry::Periodic), hop), rolloff: 0.85 }
+    }
+
+    fn samples(&self, src: &[f32], index: usize) -> Vec<f32> {
+        let start = self.stft.frame_start(index);
+        (0..self.stft.window.len() as isize).map(|n| match start + n {
+            i if i >= 0 && (i as usize) < src.len() => src[i as usize],
+            _ => 0.0
+        }).collect()
+    }
+
+    pub fn compute(&self, src: &[f32], sample_rate: u32) -> Descriptors {
+        let bins = self.stft.fft_len / 2 + 1;
+        let freqs: Vec<_> = (0..bins).map(|k| self.stft.bin_frequency(k, sample_rate)).collect();
+        let mut res = Descriptors::default();
+        let mut previous: Option<Vec<f32>> = None;
+
+        for (t, frame) in self.stft.frames(src).enumerate() {
+            let mags: Vec<f32> = frame.iter().take(bins).map(|c| c.norm()).collect();
+            let (total, energy) = (mags.iter().sum::<f32>(), mags.iter().map(|m| m * m).sum::<f32>());
+
+            let (centroid, bandwidth, rolloff, flatness, crest) = match total {
+                total if total > 0.0 => {
+                    let centroid = mags.iter().zip(freqs.iter()).map(|(m, f)| m * f).sum::<f32>() / total;
+                    let spread = mags.iter().zip(freqs.iter()).map(|(m, f)| m * (f - centroid).powi(2)).sum::<f32>() / total;
+                    let rolloff = mags.iter().zip(freqs.iter())
+                        .scan(0.0, |acc, (m, &f)| { *acc += m * m; Some((*acc, f)) })
+                        .find(|&(acc, _)| acc >= self.rolloff * energy).map_or(0.0, |(_, f)| f);
+                    let log_mean = mags.iter().map(|m| (m * m).max(1e-20).ln()).sum::<f32>() / bins as f32;
+                    let peak = mags.iter().cloned().fold(0.0, f32::max);
+                    (centroid, spread.sqrt(), rolloff, log_mean.exp() / (energy / bins as f32), peak * bins as f32 / total)
+                },
+                _ => (0.0, 0.0, 0.0, 0.0, 0.0)
+            };
+
+            let flux = previous.as_ref().map_or(0.0, |p| {
+                p.iter().zip(mags.iter()).map(|(a, b)| (b - a).powi(2)).sum::<f32>().sqrt()
+            });
+
+            let samples = self.samples(src, t);
+            let crossings = samples.windows(2).filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0)).count();
+
+            res.times.push(self.stft.frame_time(t, sample_rate));
+            res.centroid.push(centroid);
+            res.bandwidth.push(bandwidth);
+            res.rolloff.push(rolloff);
+            res.flatness.push(flatness);
+            res.flux.push(flux);
+            res.crest.push(crest);
+            res.zero_crossing_rate.push(crossings as f32 / (samples.len() - 1).max(1) as f32);
+            res.rms.push((samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt());
+            previous = Some(mags);
+        }
+        res
+    }
+
+    pub fn compute_wave(&self, wave: &Wave) -> Descriptors {
+        self.compute(&wave.data, wave.format.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DescriptorExtractor;
+    use synth::{ noise, silence, sine };
+
+    const RATE: u32 = 16000;
+
+    fn middle(series: &[f32]) -> &[f32] {
+        &series[4..series.len() - 4]
+    }
+
+    #[test]
+    fn test_sine() {
+        let extractor = DescriptorExtractor::new(1024, 256);
+        // Synthesized in f64, since f32 phase loses precision over a second of samples.
+        let src: Vec<f32> = sine(1000.0f64, 0.5, RATE, RATE as usize).into_iter().map(|x| x as f32).collect();
+        let d = extractor.compute(&src, RATE);
+        assert_eq!(d.times.len(), extractor.stft.frame_count(RATE as usize));
+        assert_eq!(d.rms.len(), d.times.len());
+
+        for i in 4..d.times.len() - 4 {
+            assert!((d.centroid[i] - 1000.0).abs() < 5.0, "{}", d.centroid[i]);
+            assert!(d.bandwidth[i] < 40.0, "{}", d.bandwidth[i]);
+            assert!((d.rolloff[i] - 1000.0).abs() <= 16.0);
+            assert!(d.flatness[i] < 1e-3);
+            assert!(d.crest[i] > 100.0);
+            assert!((d.zero_crossing_rate[i] - 2.0 * 1000.0 / RATE as f32).abs() < 2e-3);
+            assert!((d.rms[i] - 0.5 / 2f32.sqrt()).abs() < 1e-3);
+            assert!(d.flux[i] < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_noise() {
+        let d = DescriptorExtractor::new(1024, 512).compute(&noise(0.5, RATE as usize * 2, 5), RATE);
+        let mean = |s: &[f32]| s.iter().sum::<f32>() / s.len() as f32;
+
+        // Exponentially distributed periodogram values have flatness exp(-gamma).
+        assert!((mean(middle(&d.flatness)) - 0.561).abs() < 0.05);
+        assert!((mean(middle(&d.centroid)) - 4000.0).abs() < 150.0);
+        assert!((mean(middle(&d.rolloff)) - 6800.0).abs() < 200.0);
+        assert!((mean(middle(&d.zero_crossing_rate)) - 0.5).abs() < 0.02);
+        assert!((mean(middle(&d.rms)) - 0.5 / 3f32.sqrt()).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_flux_and_silence() {
+        let mut src = sine(500.0, 0.5, RATE, 8000);
+        src.extend(sine(3000.0, 0.5, RATE, 8000));
+        src.extend(silence::<f32>(8000));
+        let extractor = DescriptorExtractor::new(512, 256);
+        let d = extractor.compute(&src, RATE);
+
+        let change = d.times.iter().position(|&t| t >= 0.5).unwrap();
+        let peak = (0..d.flux.len()).fold(0, |best, i| if d.flux[i] > d.flux[best] { i } else { best });
+        assert!((peak as isize - change as isize).abs() <= 1, "{} vs {}", peak, change);
+
+        let last = d.times.len() - 1;
+        assert_eq!((d.centroid[last], d.flatness[last], d.rms[last]), (0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,131 @@
+use complex::Complex;
+use float::Float;
+use rayon::prelude::*;
+
+fn compute_weight<T: Float>(k: usize, n: usize, len: usize, sign: T) -> Complex<T> {
+    let arg = T::from_f64(2.0) * T::PI * T::from_usize(k * n % len) / T::from_usize(len);
+    Complex::from_polar(T::one(), sign * arg)
+}
+
+fn transform<T: Float>(src: &[Complex<T>], sign: T) -> Vec<Complex<T>> {
+    let len = src.len();
+    (0..len).into_par_iter().map(|k| src.iter().enumerate()
+        .fold(Complex::default(), |acc, (n, &x)| acc + x * compute_weight(k, n, len, sign))
+    ).collect()
+}
+
+/// Direct O(N^2) DFT of a real signal of any length. Slow, but a reference for `fft`.
+pub fn dft<T: Float>(src: Vec<T>) -> Vec<Complex<T>> {
+    dft_complex(&src.into_iter().map(|x| Complex::new(x, T::zero())).collect::<Vec<_>>())
+}
+
+pub fn dft_complex<T: Float>(src: &[Complex<T>]) -> Vec<Complex<T>> {
+    transform(src, -T::one())
+}
+
+/// Direct O(N^2) inverse DFT, scaled by `1 / N` like `ifft`.
+pub fn idft<T: Float>(src: Vec<Complex<T>>) -> Vec<Complex<T>> {
+    let n = T::from_usize(src.len());
+    transform(&src, T::one()).into_iter().map(|c| c / n).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ dft, dft_complex, idft };
+    use complex::Complex;
+    use float::Float;
+    use synth::noise;
+    use { fft, ifft };
+
+    fn max_error<T: Float>(a: &[Complex<T>], b: &[Complex<T>]) -> f64 {
+        assert_eq!(a.len(), b.len());
+        a.iter().zip(b.iter()).map(|(x, y)| (*x - *y).norm().to_f64()).fold(0.0, f64::max)
+    }
+
+    fn assert_fft_matches_dft<T: Float>(src: Vec<T>, tolerance: f64) {
+        let err = max_error(&fft(src.clone()), &dft(src));
+        assert!(err < tolerance, "fft error {} exceeds {}", err, tolerance);
+    }
+
+    #[test]
+    fn test_dft_known_values() {
+        let res = dft(vec![1.0f64, 2.0, 3.0]);
+        let expected = [
+            Complex::new(6.0, 0.0),
+            Complex::new(-1.5, 0.75f64.sqrt()),
+            Complex::new(-1.5, -0.75f64.sqrt())
+        ];
+        assert!(max_error(&res, &expected) < 1e-12);
+    }
+
+    #[test]
+    fn test_impulse() {
+        for &len in &[1, 2, 8, 64] {
+            for &pos in &[0, len / 2, len - 1] {
+                let src: Vec<f64> = (0..len).map(|i| if i == pos { 1.0 } else { 0.0 }).collect();
+                let expected: Vec<_> = (0..len).map(|k| Complex::from_polar(
+                    1.0, -2.0 * ::std::f64::consts::PI * (k * pos) as f64 / len as f64)).collect();
+
+                assert!(max_error(&dft(src.clone()), &expected) < 1e-12);
+                assert_fft_matches_dft(src, 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dc() {
+        let res = fft(vec![0.25f64; 32]);
+
+        assert!((res[0] - Complex::new(8.0, 0.0)).norm() < 1e-12);
+        assert!(res[1..].iter().all(|c| c.norm() < 1e-12));
+        assert_fft_matches_dft(vec![0.25f64; 32], 1e-12);
+    }
+
+    #[test]
+    fn test_sines() {
+        let len = 128;
+        for &bin in &[1, 5, 31, 63] {
+            let src: Vec<f64> = (0..len)
+                .map(|i| (2.0 * ::std::f64::consts::PI * (bin * i) as f64 / len as f64).sin()).collect();
+            let res = fft(src.clone());
+
+            assert!((res[bin] - Complex::new(0.0, -(len as f64) / 2.0)).norm() < 1e-9);
+            assert!((res[len - bin] - Complex::new(0.0, len as f64 / 2.0)).norm() < 1e-9);
+            assert_fft_matches_dft(src, 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        for &len in &[2, 16, 256, 1024] {
+            let src64: Vec<f64> = noise(1.0, len, len as u64);
+            let src32: Vec<f32> = src64.iter().map(|&x| x as f32).collect();
+
+            assert_fft_matches_dft(src64, 1e-10);
+            assert_fft_matches_dft(src32, 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_complex_input() {
+        let src: Vec<Complex<f64>> = noise(1.0, 64, 7).into_iter().zip(noise(1.0, 64, 8)).map(|(re, im)| Complex::new(re, im)).collect();
+
+        assert!(max_error(&ifft(src.clone()), &idft(src.clone())) < 1e-12);
+        assert!(max_error(&idft(dft_complex(&src)), &src) < 1e-12);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for &len in &[1, 4, 512] {
+            let src: Vec<f64> = noise(1.0, len, 1234);
+            let complex: Vec<_> = src.iter().map(|&x| Complex::new(x, 0.0)).collect();
+
+            assert!(max_error(&ifft(fft(src.clone())), &complex) < 1e-12);
+            assert!(max_error(&idft(dft(src.clone())), &complex) < 1e-12);
+        }
+
+        let src: Vec<f64> = noise(1.0, 30, 1234);
+        let complex: Vec<_> = src.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        assert!(max_error(&idft(dft(src)), &complex) < 1e-12);
+    }
+}
@@ -0,0 +1,219 @@
+use complex::Complex;
+use czt::fft_any;
+use float::Float;
+use window::{ Symmetry, hft116d };
+use Wave;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Harmonic<T> {
+    /// 2 for the second harmonic and so on.
+    pub order: usize,
+    pub frequency: T,
+    pub amplitude: T,
+    /// Level relative to the fundamental in dBc.
+    pub level: T
+}
+
+/// Distortion and noise figures of a captured sine. Ratios are in dB relative to the
+/// fundamental's power; THD and THD+N are negative, SNR and SINAD positive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distortion<T> {
+    pub fundamental: T,
+    pub amplitude: T,
+    pub harmonics: Vec<Harmonic<T>>,
+    pub thd: T,
+    pub thd_n: T,
+    pub snr: T,
+    pub sinad: T,
+    /// Effective number of bits, `(SINAD - 1.76) / 6.02`.
+    pub enob: T
+}
+
+fn percent<T: Float>(db: T) -> T {
+    T::from_f64(100.0) * T::from_f64(10.0).powf(db / T::from_f64(20.0))
+}
+
+impl<T: Float> Distortion<T> {
+    pub fn thd_percent(&self) -> T { percent(self.thd) }
+
+    pub fn thd_n_percent(&self) -> T { percent(self.thd_n) }
+}
+
+/// Measures a sine through an HFT116D flat-top windowed FFT of the whole capture. The
+/// power of each tone is summed over `span` bins either side of it, harmonics up to
+/// order `harmonics` below Nyquist are counted as distortion, and all other bins apart
+/// from DC are noise, scaled up to make up for the bins taken by the tones. The
+/// fundamental must lie more than `2 * span` bins above DC, so that its bins stay apart
+/// from those of DC and the second harmonic.
+#[derive(Clone, Debug)]
+pub struct DistortionAnalyzer {
+    pub harmonics: usize,
+    pub span: usize
+}
+
+impl Default for DistortionAnalyzer {
+    fn default() -> Self {
+        DistortionAnalyzer { harmonics: 10, span: 8 }
+    }
+}
+
+impl DistortionAnalyzer {
+    /// One-sided power per bin, so that the bins around a tone sum to its mean power.
+    fn power<T: Float>(&self, src: &[T]) -> Vec<T> {
+        let window: Vec<T> = hft116d(src.len(), Symmetry::Periodic);
+        let scale = T::from_f64(2.0) / (T::from_usize(src.len()) * window.iter().map(|&w| w * w).sum::<T>());
+        let frame = src.iter().zip(window.iter()).map(|(&x, &w)| Complex::new(x * w, T::zero())).collect();
+        fft_any(frame).into_iter().take(src.len() / 2 + 1).map(|c| c.norm_sqr() * scale).collect()
+    }
+
+    /// Analyzes `src`, taking the fundamental near `fundamental` Hz or, when `None`, at
+    /// the strongest bin above DC.
+    pub fn analyze<T: Float>(&self, src: &[T], sample_rate: u32, fundamental: Option<T>) -> Distortion<T> {
+        assert!(src.len() >= 4 * self.span + 2, "capture of {} samples is too short", src.len());
+        let power = self.power(src);
+        let width = T::from_f64(sample_rate as f64) / T::from_usize(src.len());
+        let last = power.len() - 1;
+        let range = |k: usize| k.saturating_sub(self.span)..(k + self.span).min(last) + 1;
+        let tone = |k: usize| range(k).map(|i| power[i]).sum::<T>();
+
+        let peak = match fundamental {
+            Some(f) => {
+                assert!(f > T::zero() && f < T::from_f64(sample_rate as f64 / 2.0), "fundamental must lie between DC and Nyquist");
+                (f / width).round().to_f64() as usize
+            },
+            None => (2 * self.span + 1..last + 1).fold(2 * self.span + 1, |best, k| if power[k] > power[best] { k } else { best })
+        };
+        assert!(peak > 2 * self.span, "fundamental is within {} bins of DC, where its bins overlap its harmonics'", 2 * self.span);
+        let centroid = range(peak).map(|i| T::from_usize(i) * power[i]).sum::<T>() / tone(peak);
+        let f0 = centroid * width;
+        let signal = tone(peak);
+
+        let mut excluded = vec![false; power.len()];
+        let mut exclude = |k: usize| range(k).for_each(|i| excluded[i] = true);
+        exclude(0);
+        exclude(peak);
+
+        let db = |ratio: T| T::from_f64(10.0) * ratio.max(T::from_f64(1e-30)).log10();
+        let amplitude = |p: T| (T::from_f64(2.0) * p).sqrt();
+        let harmonics: Vec<_> = (2..self.harmonics + 1)
+            .map(|order| (order, (centroid * T::from_usize(order)).round().to_f64() as usize))
+            .take_while(|&(_, k)| k <= last)
+            .map(|(order, k)| {
+                exclude(k);
+                let p = tone(k);
+                Harmonic { order, frequency: f0 * T::from_usize(order), amplitude: amplitude(p), level: db(p / signal) }
+            }).collect();
+        let distortion = harmonics.iter().map(|h| h.amplitude * h.amplitude / T::from_f64(2.0)).sum::<T>();
+
+        let dc = range(0).count();
+        let free = excluded.iter().filter(|&&e| !e).count();
+        let noise = match free {
+            0 => T::zero(),
+            free => power.iter().zip(excluded.iter()).filter(|&(_, &e)| !e).map(|(&p, _)| p).sum::<T>()
+                * T::from_usize(power.len() - dc) / T::from_usize(free)
+        };
+
+        let sinad = db(signal / (distortion + noise));
+        Distortion {
+            fundamental: f0,
+            amplitude: amplitude(signal),
+            harmonics,
+            thd: db(distortion / signal),
+            thd_n: -sinad,
+            snr: db(signal / noise),
+            sinad,
+            enob: (sinad - T::from_f64(1.76)) / T::from_f64(6.02)
+        }
+    }
+
+    pub fn analyze_wave(&self, wave: &Wave, fundamental: Option<f32>) -> Distortion<f32> {
+        self.analyze(&wave.data, wave.format.sample_rate, fundamental)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DistortionAnalyzer;
+    use synth::{ harmonics, mix, noise, sine };
+
+    const RATE: u32 = 48000;
+    const LEN: usize = 48000;
+
+    #[test]
+    fn test_harmonic_distortion() {
+        // 1% second and 0.1% third harmonic: THD = 20 log10(sqrt(1e-4 + 1e-6)).
+        let src = harmonics(997.3f64, &[0.8, 0.008, 0.0008], RATE, LEN);
+        let res = DistortionAnalyzer::default().analyze(&src, RATE, None);
+
+        assert!((res.fundamental - 997.3).abs() < 0.01, "{}", res.fundamental);
+        assert!((res.amplitude - 0.8).abs() < 1e-4, "{}", res.amplitude);
+        assert_eq!(res.harmonics.len(), 9);
+        assert!((res.harmonics[0].level + 40.0).abs() < 0.01, "{}", res.harmonics[0].level);
+        assert!((res.harmonics[1].level + 60.0).abs() < 0.01);
+        assert!((res.harmonics[1].frequency - 3.0 * 997.3).abs() < 0.05);
+        assert!(res.harmonics[2..].iter().all(|h| h.level < -120.0));
+
+        let expected = 10.0 * (1e-4f64 + 1e-6).log10();
+        assert!((res.thd - expected).abs() < 0.01, "{} != {}", res.thd, expected);
+        assert!((res.thd_percent() - 1.005).abs() < 1e-3);
+        assert!((res.thd_n - res.thd).abs() < 0.01);
+        assert!(res.snr > 110.0, "{}", res.snr);
+    }
+
+    #[test]
+    fn test_noise() {
+        // Uniform noise of amplitude a has power a^2 / 3.
+        let src = mix(&[sine(1234.5, 1.0, RATE, LEN), noise(0.01, LEN, 3)]);
+        let res = DistortionAnalyzer::default().analyze(&src, RATE, Some(1234.5));
+        let expected = 10.0 * (0.5f64 / (1e-4 / 3.0)).log10();
+
+        assert!((res.snr - expected).abs() < 0.2, "{} != {}", res.snr, expected);
+        assert!((res.sinad - expected).abs() < 0.3, "{} != {}", res.sinad, expected);
+        assert!((res.thd_n + res.sinad).abs() < 1e-9);
+        assert!(res.thd < -60.0);
+    }
+
+    #[test]
+    fn test_enob_of_quantized_sine() {
+        let step = 2.0f64 / 4096.0;
+        let src: Vec<f64> = sine(997.3, 1.0 - step, RATE, LEN).into_iter().map(|x| (x / step).round() * step).collect();
+        let res = DistortionAnalyzer::default().analyze(&src, RATE, None);
+        assert!((res.enob - 12.0).abs() < 0.05, "{}", res.enob);
+    }
+
+    #[test]
+    fn test_clipped_sine() {
+        let src: Vec<f32> = sine(500.0f64, 1.0, RATE, LEN).into_iter().map(|x| x.clamp(-0.9, 0.9) as f32).collect();
+        let res = DistortionAnalyzer::default().analyze(&src, RATE, Some(500.0));
+        // Symmetric clipping only adds odd harmonics.
+        assert!(res.harmonics[1].level > -40.0 && res.harmonics[0].level < -90.0);
+        assert!(res.thd > -30.0 && res.thd < -15.0, "{}", res.thd);
+    }
+
+    #[test]
+    #[should_panic(expected = "between DC and Nyquist")]
+    fn test_fundamental_above_nyquist() {
+        DistortionAnalyzer::default().analyze(&sine(1000.0f64, 1.0, RATE, LEN), RATE, Some(24000.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "between DC and Nyquist")]
+    fn test_negative_fundamental() {
+        DistortionAnalyzer::default().analyze(&sine(1000.0f64, 1.0, RATE, LEN), RATE, Some(-1000.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "within 16 bins of DC")]
+    fn test_fundamental_near_dc() {
+        // At 1 Hz per bin the second harmonic of 12 Hz would sum the fundamental's bins.
+        DistortionAnalyzer::default().analyze(&sine(12.0f64, 1.0, RATE, LEN), RATE, Some(12.0));
+    }
+
+    #[test]
+    fn test_search_above_dc() {
+        // A stronger tone too close to DC is passed over for the one that can be measured.
+        let src = mix(&[sine(12.0f64, 1.0, RATE, LEN), sine(1000.0, 0.5, RATE, LEN)]);
+        let res = DistortionAnalyzer::default().analyze(&src, RATE, None);
+        assert!((res.fundamental - 1000.0).abs() < 0.1, "{}", res.fundamental);
+    }
+}
@@ -0,0 +1,173 @@
+use goertzel::goertzel_power;
+use Wave;
+
+pub const ROW_FREQUENCIES: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+pub const COLUMN_FREQUENCIES: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+
+const KEYS: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D']
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DtmfDigit {
+    pub digit: char,
+    pub start: f32,
+    pub end: f32
+}
+
+/// Block-wise DTMF detector. Each block is accepted only if it passes the usual
+/// checks: tone level, twist between the row and column tones, the share of block
+/// energy held by the two tones, and the margin over the other tones in each group.
+#[derive(Clone, Debug)]
+pub struct DtmfDecoder {
+    pub block_duration: f32,
+    pub min_blocks: usize,
+    pub min_level_db: f32,
+    pub max_twist_db: f32,
+    pub max_reverse_twist_db: f32,
+    pub min_energy_ratio: f32,
+    pub min_relative_peak_db: f32
+}
+
+impl Default for DtmfDecoder {
+    fn default() -> Self {
+        DtmfDecoder {
+            block_duration: 0.0125,
+            min_blocks: 2,
+            min_level_db: -40.0,
+            max_twist_db: 8.0,
+            max_reverse_twist_db: 4.0,
+            min_energy_ratio: 0.7,
+            min_relative_peak_db: 6.0
+        }
+    }
+}
+
+fn db(power_ratio: f32) -> f32 { 10.0 * power_ratio.log10() }
+
+fn strongest(powers: &[f32]) -> (usize, f32, f32) {
+    let (idx, &peak) = powers.iter().enumerate()
+        .fold((0, &0.0), |best, cur| if cur.1 > best.1 { cur } else { best });
+    let others = powers.iter().enumerate().filter(|&(i, _)| i != idx)
+        .fold(0.0f32, |acc, (_, &p)| acc.max(p));
+    (idx, peak, others)
+}
+
+impl DtmfDecoder {
+    pub fn detect_block(&self, block: &[f32], sample_rate: u32) -> Option<char> {
+        let tone_energy = |freq: &f32| 2.0 * goertzel_power(block, *freq, sample_rate) / block.len() as f32;
+        let (rows, cols): (Vec<_>, Vec<_>) = (
+            ROW_FREQUENCIES.iter().map(tone_energy).collect(),
+            COLUMN_FREQUENCIES.iter().map(tone_energy).collect()
+        );
+        let ((r, row, row_others), (c, col, col_others)) = (strongest(&rows), strongest(&cols));
+        let total: f32 = block.iter().map(|x| x * x).sum();
+        let level = |energy: f32| db(energy / block.len() as f32);
+
+        let accepted = level(row) >= self.min_level_db && level(col) >= self.min_level_db
+            && db(row / col) <= self.max_twist_db
+            && db(col / row) <= self.max_reverse_twist_db
+            && (row + col) >= self.min_energy_ratio * total
+            && db(row / row_others) >= self.min_relative_peak_db
+            && db(col / col_others) >= self.min_relative_peak_db;
+
+        match accepted {
+            true => Some(KEYS[r][c]),
+            false => None
+        }
+    }
+
+    pub fn decode(&self, wave: &Wave) -> Vec<DtmfDigit> {
+        let rate = wave.format.sample_rate;
+        let block_len = ((self.block_duration * rate as f32) as usize).max(1);
+        let seconds = |block: usize| (block * block_len) as f32 / rate as f32;
+
+        let detections: Vec<_> = wave.data.chunks(block_len).filter(|b| b.len() == block_len)
+            .map(|b| self.detect_block(b, rate)).collect();
+
+        let mut digits = Vec::new();
+        let mut run: Option<(char, usize)> = None;
+        for (i, &det) in detections.iter().chain(Some(None).iter()).enumerate() {
+            match (run, det) {
+                (Some((d, _)), Some(cur)) if d == cur => continue,
+                (Some((d, start)), _) => {
+                    if i - start >= self.min_blocks {
+                        digits.push(DtmfDigit { digit: d, start: seconds(start), end: seconds(i) });
+                    }
+                    run = det.map(|cur| (cur, i));
+                },
+                (None, _) => run = det.map(|cur| (cur, i))
+            }
+        }
+        digits
+    }
+}
+
+pub fn decode_dtmf(wave: &Wave) -> Vec<DtmfDigit> {
+    DtmfDecoder::default().decode(wave)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ COLUMN_FREQUENCIES, DtmfDecoder, KEYS, ROW_FREQUENCIES, decode_dtmf };
+    use synth::{ mix, silence, sine };
+    use Wave;
+
+    const RATE: u32 = 8000;
+
+    fn tone(digit: char, row_amp: f32, col_amp: f32, len: usize) -> Vec<f32> {
+        let (r, c) = (0..4).flat_map(|r| (0..4).map(move |c| (r, c)))
+            .find(|&(r, c)| KEYS[r][c] == digit).unwrap();
+        mix(&[sine(ROW_FREQUENCIES[r], row_amp, RATE, len), sine(COLUMN_FREQUENCIES[c], col_amp, RATE, len)])
+    }
+
+    fn sequence(digits: &str, tone_len: usize, gap_len: usize) -> Vec<f32> {
+        digits.chars().fold(silence(gap_len), |mut acc, d| {
+            acc.extend(tone(d, 0.3, 0.3, tone_len));
+            acc.extend(silence::<f32>(gap_len));
+            acc
+        })
+    }
+
+    #[test]
+    fn test_decode_all_keys() {
+        let keys = "123A456B789C*0#D";
+        let res = decode_dtmf(&Wave::from_samples(sequence(keys, 400, 400), RATE));
+
+        assert_eq!(res.iter().map(|d| d.digit).collect::<String>(), keys);
+        for (i, d) in res.iter().enumerate() {
+            let start = (400 + i * 800) as f32 / RATE as f32;
+            assert!((d.start - start).abs() <= 0.0125 && (d.end - (start + 0.05)).abs() <= 0.0125);
+        }
+    }
+
+    #[test]
+    fn test_repeated_digits() {
+        let res = decode_dtmf(&Wave::from_samples(sequence("5500", 480, 320), RATE));
+        assert_eq!(res.iter().map(|d| d.digit).collect::<String>(), "5500");
+    }
+
+    #[test]
+    fn test_twist_rejected() {
+        let decoder = DtmfDecoder::default();
+        let block = |row_amp, col_amp| tone('8', row_amp, col_amp, 100);
+
+        assert_eq!(decoder.detect_block(&block(0.3, 0.3), RATE), Some('8'));
+        assert_eq!(decoder.detect_block(&block(0.3, 0.09), RATE), None);
+        assert_eq!(decoder.detect_block(&block(0.15, 0.3), RATE), None);
+    }
+
+    #[test]
+    fn test_energy_ratio_rejected() {
+        let decoder = DtmfDecoder::default();
+        let (digit, noise) = (tone('2', 0.2, 0.2, 100), sine(1000.0, 0.3, RATE, 100));
+
+        assert_eq!(decoder.detect_block(&digit, RATE), Some('2'));
+        assert_eq!(decoder.detect_block(&mix(&[digit, noise]), RATE), None);
+        assert_eq!(decoder.detect_block(&sine(697.0, 0.3, RATE, 100), RATE), None);
+        assert_eq!(decoder.detect_block(&silence(100), RATE), None);
+    }
+}
@@ -0,0 +1,432 @@
+use complex::Complex;
+use float::Float;
+use goertzel::goertzel_generalized;
+use window::{ Symmetry, blackman, hamming, hann, kaiser, rectangular };
+use { fft, sinc };
+
+/// Ideal response to design for, with edges in Hz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Response<T> {
+    LowPass(T),
+    HighPass(T),
+    /// Lower and upper edge of the passband.
+    BandPass(T, T),
+    /// Lower and upper edge of the stopband.
+    BandStop(T, T)
+}
+
+impl<T: Float> Response<T> {
+    fn edges(&self) -> Vec<T> {
+        match *self {
+            Response::LowPass(f) | Response::HighPass(f) => vec![f],
+            Response::BandPass(lo, hi) | Response::BandStop(lo, hi) => vec![lo, hi]
+        }
+    }
+
+    /// Ideal impulse response `m` samples from its centre.
+    fn ideal(&self, m: T, sample_rate: T) -> T {
+        let two = T::from_f64(2.0);
+        let lpf = |f: T| two * f / sample_rate * sinc(two * T::PI * f / sample_rate * m);
+        let impulse = if m == T::zero() { T::one() } else { T::zero() };
+        match *self {
+            Response::LowPass(f) => lpf(f),
+            Response::HighPass(f) => impulse - lpf(f),
+            Response::BandPass(lo, hi) => lpf(hi) - lpf(lo),
+            Response::BandStop(lo, hi) => impulse - lpf(hi) + lpf(lo)
+        }
+    }
+
+    /// Frequency in the passband where the designed filter has unit gain.
+    fn reference(&self, sample_rate: T) -> T {
+        match *self {
+            Response::LowPass(_) | Response::BandStop(_, _) => T::zero(),
+            Response::HighPass(_) => sample_rate / T::from_f64(2.0),
+            Response::BandPass(lo, hi) => (lo + hi) / T::from_f64(2.0)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FirWindow {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman
+}
+
+impl FirWindow {
+    /// Transition width in cycles per sample times the tap count; Hann's is the 3.1
+    /// of the examples' `3.1 / delta`.
+    pub fn transition_factor(&self) -> f64 {
+        match *self {
+            FirWindow::Rectangular => 0.9,
+            FirWindow::Hann => 3.1,
+            FirWindow::Hamming => 3.3,
+            FirWindow::Blackman => 5.5
+        }
+    }
+
+    pub fn coefficients<T: Float>(&self, len: usize) -> Vec<T> {
+        match *self {
+            FirWindow::Rectangular => rectangular(len),
+            FirWindow::Hann => hann(len, Symmetry::Symmetric),
+            FirWindow::Hamming => hamming(len, Symmetry::Symmetric),
+            FirWindow::Blackman => blackman(len, Symmetry::Symmetric)
+        }
+    }
+}
+
+/// `H(freq)` of an FIR filter, with the phase of its first tap.
+pub fn frequency_response<T: Float>(filter: &[T], freq: T, sample_rate: u32) -> Complex<T> {
+    goertzel_generalized(filter, freq * T::from_usize(filter.len()) / T::from_f64(sample_rate as f64))
+}
+
+/// Windowed-sinc design of a linear phase FIR filter. The tap count is always odd, the
+/// coefficients symmetric, and the passband gain one: at DC for low-pass and band-stop,
+/// at Nyquist for high-pass and between the edges for band-pass.
+#[derive(Clone, Debug)]
+pub struct FirDesign<T> {
+    pub response: Response<T>,
+    pub sample_rate: u32,
+    pub window: FirWindow,
+    /// Transition width in Hz, from which the tap count follows unless `taps` is set.
+    pub transition: T,
+    pub taps: Option<usize>
+}
+
+impl<T: Float> FirDesign<T> {
+    /// Hamming windowed, with as many taps as `transition` needs.
+    pub fn new(response: Response<T>, sample_rate: u32, transition: T) -> Self {
+        assert!(transition > T::zero(), "transition width must be positive");
+        FirDesign { response, sample_rate, window: FirWindow::Hamming, transition, taps: None }
+    }
+
+    pub fn with_window(mut self, window: FirWindow) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Fixes the tap count, rounded up to an odd number.
+    pub fn with_taps(mut self, taps: usize) -> Self {
+        self.taps = Some(taps);
+        self
+    }
+
+    pub fn tap_count(&self) -> usize {
+        let taps = self.taps.unwrap_or_else(|| {
+            (self.window.transition_factor() * self.sample_rate as f64 / self.transition.to_f64()).ceil() as usize
+        });
+        taps | 1
+    }
+
+    pub fn design(&self) -> Vec<T> {
+        let window = self.window.coefficients(self.tap_count());
+        design_windowed(&self.response, self.sample_rate, &window)
+    }
+}
+
+/// Ideal response times a symmetric `window` of odd length, mirrored about its centre
+/// tap and normalized to unit passband gain.
+fn design_windowed<T: Float>(response: &Response<T>, sample_rate: u32, window: &[T]) -> Vec<T> {
+    let rate = T::from_f64(sample_rate as f64);
+    let edges = response.edges();
+    assert!(edges.iter().all(|&f| f > T::zero() && f < rate / T::from_f64(2.0)), "edges must lie between DC and Nyquist");
+    assert!(edges.windows(2).all(|w| w[0] < w[1]), "band edges must rise");
+    assert!(window.len() % 2 == 1, "linear phase design needs an odd tap count, got {}", window.len());
+
+    let half = window.len() / 2;
+    let mut filter: Vec<T> = (0..half + 1)
+        .map(|n| response.ideal(T::from_f64(n as f64 - half as f64), rate) * window[n]).collect();
+    let mirrored: Vec<T> = filter[..half].iter().rev().cloned().collect();
+    filter.extend(mirrored);
+
+    let gain = frequency_response(&filter, response.reference(rate), sample_rate).norm();
+    filter.into_iter().map(|h| h / gain).collect()
+}
+
+/// Kaiser's estimate of the window shape for `attenuation` dB of stopband rejection.
+pub fn kaiser_beta<T: Float>(attenuation: T) -> T {
+    match attenuation.to_f64() {
+        a if a > 50.0 => T::from_f64(0.1102 * (a - 8.7)),
+        a if a >= 21.0 => T::from_f64(0.5842 * (a - 21.0).powf(0.4) + 0.07886 * (a - 21.0)),
+        _ => T::zero()
+    }
+}
+
+/// `(from, to)` in Hz.
+type Band<T> = (T, T);
+
+/// A Kaiser windowed filter with the ripple and attenuation read off its response.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KaiserFilter<T> {
+    pub taps: Vec<T>,
+    pub beta: T,
+    /// Largest passband deviation from unit gain in dB.
+    pub ripple: T,
+    /// Smallest stopband rejection in dB.
+    pub attenuation: T
+}
+
+/// FIR design from a specification. `passband` holds the passband edges and `stopband`
+/// the matching stopband edges, as the same kind of response, and the stopband should
+/// reject `attenuation` dB, with passband ripple of about the same fraction of unity.
+/// Kaiser's estimates can miss by a dB or two, which the measured figures of
+/// `KaiserFilter` show.
+#[derive(Clone, Debug)]
+pub struct KaiserDesign<T> {
+    pub passband: Response<T>,
+    pub stopband: Response<T>,
+    pub sample_rate: u32,
+    pub attenuation: T
+}
+
+impl<T: Float> KaiserDesign<T> {
+    pub fn new(passband: Response<T>, stopband: Response<T>, sample_rate: u32, attenuation: T) -> Self {
+        KaiserDesign { passband, stopband, sample_rate, attenuation }
+    }
+
+    pub fn beta(&self) -> T {
+        kaiser_beta(self.attenuation)
+    }
+
+    /// Width of the narrowest transition band in Hz.
+    pub fn transition(&self) -> T {
+        let outward = match (self.passband, self.stopband) {
+            (Response::LowPass(_), Response::LowPass(_)) => vec![T::one()],
+            (Response::HighPass(_), Response::HighPass(_)) => vec![-T::one()],
+            (Response::BandPass(_, _), Response::BandPass(_, _)) => vec![-T::one(), T::one()],
+            (Response::BandStop(_, _), Response::BandStop(_, _)) => vec![T::one(), -T::one()],
+            _ => panic!("passband and stopband must be the same kind of response")
+        };
+        let widths = self.passband.edges().into_iter().zip(self.stopband.edges()).zip(outward)
+            .map(|((pass, stop), sign)| (stop - pass) * sign);
+        let width = widths.fold(T::INFINITY, T::min);
+        assert!(width > T::zero(), "stopband edges must lie beyond the passband edges");
+        width
+    }
+
+    /// Kaiser's order estimate `(A - 7.95) / (2.285 * 2 pi * transition / rate)`, plus one
+    /// and rounded up to odd.
+    pub fn tap_count(&self) -> usize {
+        let width = 2.0 * ::std::f64::consts::PI * self.transition().to_f64() / self.sample_rate as f64;
+        let order = ((self.attenuation.to_f64() - 7.95).max(0.0) / (2.285 * width)).ceil() as usize;
+        (order + 1) | 1
+    }
+
+    /// Cutoffs of the windowed-sinc design, halfway across each transition band.
+    fn cutoffs(&self) -> Response<T> {
+        let mid = |pass: T, stop: T| (pass + stop) / T::from_f64(2.0);
+        match (self.passband, self.stopband) {
+            (Response::LowPass(p), Response::LowPass(s)) => Response::LowPass(mid(p, s)),
+            (Response::HighPass(p), Response::HighPass(s)) => Response::HighPass(mid(p, s)),
+            (Response::BandPass(lo, hi), Response::BandPass(slo, shi)) => Response::BandPass(mid(lo, slo), mid(hi, shi)),
+            (Response::BandStop(lo, hi), Response::BandStop(slo, shi)) => Response::BandStop(mid(lo, slo), mid(hi, shi)),
+            _ => panic!("passband and stopband must be the same kind of response")
+        }
+    }
+
+    /// Passbands and stopbands.
+    fn bands(&self) -> (Vec<Band<T>>, Vec<Band<T>>) {
+        let nyquist = T::from_f64(self.sample_rate as f64 / 2.0);
+        match (self.passband, self.stopband) {
+            (Response::LowPass(p), Response::LowPass(s)) => (vec![(T::zero(), p)], vec![(s, nyquist)]),
+            (Response::HighPass(p), Response::HighPass(s)) => (vec![(p, nyquist)], vec![(T::zero(), s)]),
+            (Response::BandPass(lo, hi), Response::BandPass(slo, shi)) =>
+                (vec![(lo, hi)], vec![(T::zero(), slo), (shi, nyquist)]),
+            (Response::BandStop(lo, hi), Response::BandStop(slo, shi)) =>
+                (vec![(T::zero(), lo), (hi, nyquist)], vec![(slo, shi)]),
+            _ => panic!("passband and stopband must be the same kind of response")
+        }
+    }
+
+    pub fn design(&self) -> KaiserFilter<T> {
+        let (passbands, stopbands) = self.bands();
+        let nyquist = T::from_f64(self.sample_rate as f64 / 2.0);
+        assert!(stopbands.iter().all(|&(from, to)| from >= T::zero() && from < to && to <= nyquist),
+            "{:?} leaves no stopband", self.stopband.edges().iter().map(|f| f.to_f64()).collect::<Vec<_>>());
+
+        let (beta, len) = (self.beta(), self.tap_count());
+        let taps = design_windowed(&self.cutoffs(), self.sample_rate, &kaiser(len, beta, Symmetry::Symmetric));
+
+        // The response on a grid at least 16 times finer than the taps.
+        let size = (16 * len).next_power_of_two().max(8192);
+        let mut padded = taps.clone();
+        padded.resize(size, T::zero());
+        let step = T::from_f64(self.sample_rate as f64) / T::from_usize(size);
+        let db: Vec<T> = fft(padded).into_iter().take(size / 2 + 1).map(|c| c.db()).collect();
+        let within = |bands: &[Band<T>], k: usize| bands.iter().any(|&(from, to)| {
+            let f = T::from_usize(k) * step;
+            f >= from && f <= to
+        });
+
+        let ripple = (0..db.len()).filter(|&k| within(&passbands, k)).map(|k| db[k].abs()).fold(T::zero(), T::max);
+        let attenuation = (0..db.len()).filter(|&k| within(&stopbands, k)).map(|k| -db[k]).fold(T::INFINITY, T::min);
+        KaiserFilter { taps, beta, ripple, attenuation }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ FirDesign, FirWindow, KaiserDesign, Response, frequency_response, kaiser_beta };
+
+    const RATE: u32 = 48000;
+
+    fn gain(filter: &[f64], freq: f64) -> f64 {
+        frequency_response(filter, freq, RATE).db()
+    }
+
+    fn assert_linear_phase(filter: &[f64]) {
+        assert_eq!(filter.len() % 2, 1);
+        assert!(filter.iter().zip(filter.iter().rev()).all(|(a, b)| a == b));
+    }
+
+    #[test]
+    fn test_low_pass() {
+        let design = FirDesign::new(Response::LowPass(4000.0), RATE, 1000.0);
+        assert_eq!(design.tap_count(), 159);
+        let filter = design.design();
+        assert_linear_phase(&filter);
+        assert!((filter.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+
+        for &f in &[0.0, 1000.0, 3000.0, 3500.0] {
+            assert!(gain(&filter, f).abs() < 0.05, "{} Hz: {} dB", f, gain(&filter, f));
+        }
+        assert!((gain(&filter, 4000.0) + 6.0).abs() < 0.1);
+        for &f in &[4500.0, 6000.0, 12000.0, 23000.0] {
+            assert!(gain(&filter, f) < -50.0, "{} Hz: {} dB", f, gain(&filter, f));
+        }
+    }
+
+    #[test]
+    fn test_high_pass() {
+        let filter = FirDesign::new(Response::HighPass(2000.0), RATE, 500.0).with_taps(200).design();
+        assert_eq!(filter.len(), 201);
+        assert_linear_phase(&filter);
+        assert!(gain(&filter, 24000.0).abs() < 1e-9);
+        assert!(gain(&filter, 0.0) < -50.0 && gain(&filter, 1000.0) < -50.0);
+        assert!(gain(&filter, 3000.0).abs() < 0.05 && gain(&filter, 15000.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_band_pass_and_stop() {
+        let (lo, hi) = (3000.0, 6000.0);
+        let pass = FirDesign::new(Response::BandPass(lo, hi), RATE, 800.0).with_window(FirWindow::Blackman).design();
+        let stop = FirDesign::new(Response::BandStop(lo, hi), RATE, 800.0).with_window(FirWindow::Blackman).design();
+        assert_linear_phase(&pass);
+        assert_linear_phase(&stop);
+
+        assert!(gain(&pass, 4500.0).abs() < 1e-9 && gain(&stop, 0.0).abs() < 1e-9);
+        for &f in &[0.0, 2000.0, 7000.0, 24000.0] {
+            assert!(gain(&pass, f) < -70.0 && gain(&stop, f).abs() < 0.01, "{} Hz", f);
+        }
+        for &f in &[3500.0, 4500.0, 5500.0] {
+            assert!(gain(&pass, f).abs() < 0.01 && gain(&stop, f) < -70.0, "{} Hz", f);
+        }
+        // The two are complementary: together they pass everything unchanged.
+        let centre = pass.len() / 2;
+        for (n, (a, b)) in pass.iter().zip(stop.iter()).enumerate() {
+            let expected = if n == centre { 1.0 } else { 0.0 };
+            assert!((a + b - expected).abs() < 1e-3, "{}: {}", n, a + b);
+        }
+    }
+
+    #[test]
+    fn test_window_trade_off() {
+        // At equal length Blackman attenuates more than Hann but needs a wider transition.
+        let design = |window| FirDesign::new(Response::LowPass(6000.0), RATE, 1.0)
+            .with_window(window).with_taps(101).design();
+        let (hann, blackman) = (design(FirWindow::Hann), design(FirWindow::Blackman));
+        let highest = |filter: &[f64]| (150..480).map(|k| gain(filter, 50.0 * k as f64)).fold(f64::NEG_INFINITY, f64::max);
+        assert!(highest(&blackman) < highest(&hann) - 15.0, "{} {}", highest(&blackman), highest(&hann));
+        assert!(gain(&blackman, 6500.0) > gain(&hann, 6500.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "band edges must rise")]
+    fn test_reversed_edges() {
+        FirDesign::new(Response::BandPass(5000.0, 3000.0), RATE, 500.0).design();
+    }
+
+    #[test]
+    #[should_panic(expected = "transition width must be positive")]
+    fn test_without_transition() {
+        FirDesign::new(Response::LowPass(4000.0), RATE, 0.0);
+    }
+
+    #[test]
+    fn test_kaiser_parameters() {
+        assert_eq!(kaiser_beta(20.0f64), 0.0);
+        assert!((kaiser_beta(30.0f64) - 2.1166).abs() < 1e-4, "{}", kaiser_beta(30.0));
+        assert!((kaiser_beta(80.0f64) - 7.857).abs() < 1e-3);
+
+        // An order of 52.05 / (2.285 * 2 pi / 48) = 174.02, so 176 taps made odd.
+        let design = KaiserDesign::new(Response::LowPass(4000.0), Response::LowPass(5000.0), RATE, 60.0);
+        assert_eq!(design.tap_count(), 177);
+        let more = KaiserDesign { attenuation: 90.0, ..design.clone() };
+        let narrower = KaiserDesign { stopband: Response::LowPass(4500.0), ..design };
+        assert!(more.tap_count() > 250 && narrower.tap_count() > 340);
+    }
+
+    #[test]
+    fn test_kaiser_meets_specification() {
+        let responses = [
+            (Response::LowPass(4000.0), Response::LowPass(5200.0)),
+            (Response::HighPass(6000.0), Response::HighPass(4800.0)),
+            (Response::BandPass(3000.0, 9000.0), Response::BandPass(1800.0, 10200.0)),
+            (Response::BandStop(3000.0, 9000.0), Response::BandStop(4200.0, 7800.0)),
+            (Response::BandPass(3000.0, 9000.0), Response::BandPass(1000.0, 10200.0)),
+            (Response::BandStop(3000.0, 9000.0), Response::BandStop(5000.0, 7800.0))
+        ];
+        for &(passband, stopband) in &responses {
+            for &attenuation in &[40.0, 60.0, 90.0] {
+                let res = KaiserDesign::new(passband, stopband, RATE, attenuation).design();
+                assert_linear_phase(&res.taps);
+                // Kaiser's estimates land within a couple of dB of the target, for the
+                // stopband and the passband ripple alike.
+                assert!(res.attenuation > attenuation - 2.0, "{:?} {}: {} dB", stopband, attenuation, res.attenuation);
+                let ripple = 20.0 * (1.0 + 10f64.powf(-(attenuation - 3.0) / 20.0)).log10();
+                assert!(res.ripple < ripple, "{:?} {}: {} dB", passband, attenuation, res.ripple);
+            }
+        }
+    }
+
+    #[test]
+    fn test_kaiser_reports_response() {
+        let res = KaiserDesign::new(Response::LowPass(4000.0), Response::LowPass(5000.0), RATE, 60.0).design();
+        let stop = (0..191).map(|k| -gain(&res.taps, 5000.0 + 100.0 * k as f64)).fold(f64::INFINITY, f64::min);
+        let pass = (0..41).map(|k| gain(&res.taps, 100.0 * k as f64).abs()).fold(0.0, f64::max);
+        assert!(stop >= res.attenuation - 0.01 && stop < res.attenuation + 3.0, "{} vs {}", stop, res.attenuation);
+        assert!(pass <= res.ripple + 1e-4, "{} vs {}", pass, res.ripple);
+    }
+
+    #[test]
+    fn test_kaiser_unequal_transitions() {
+        // The narrower transition sets the length, and each cutoff sits midway across its own.
+        let design = KaiserDesign::new(Response::BandPass(3000.0, 9000.0), Response::BandPass(1000.0, 9600.0), RATE, 60.0);
+        let even = KaiserDesign::new(Response::BandPass(3000.0, 9000.0), Response::BandPass(2400.0, 9600.0), RATE, 60.0);
+        assert_eq!(design.transition(), 600.0);
+        assert_eq!(design.tap_count(), even.tap_count());
+        assert_eq!(design.cutoffs(), Response::BandPass(2000.0, 9300.0));
+
+        let (wide, narrow) = (design.design(), even.design());
+        assert!(gain(&wide.taps, 2000.0) > gain(&narrow.taps, 2000.0) + 20.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "leaves no stopband")]
+    fn test_kaiser_without_stopband() {
+        KaiserDesign::new(Response::BandStop(3000.0, 4000.0), Response::BandStop(3600.0, 3400.0), RATE, 60.0).design();
+    }
+
+    #[test]
+    #[should_panic(expected = "stopband edges must lie beyond the passband edges")]
+    fn test_kaiser_stopband_inside_passband() {
+        KaiserDesign::new(Response::HighPass(6000.0), Response::HighPass(6500.0), RATE, 60.0).design();
+    }
+
+    #[test]
+    #[should_panic(expected = "same kind of response")]
+    fn test_kaiser_mismatched_responses() {
+        KaiserDesign::new(Response::LowPass(4000.0), Response::HighPass(5000.0), RATE, 60.0).design();
+    }
+}
@@ -0,0 +1,83 @@
+use simd::Kernels;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{ Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, Sub, SubAssign };
+
+pub trait Float: Copy + Default + PartialOrd + fmt::Debug + fmt::Display + Send + Sync + Sum
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+    + Rem<Output = Self> + Neg<Output = Self>
+    + AddAssign + SubAssign + MulAssign + DivAssign + Kernels {
+    const PI: Self;
+    const EPSILON: Self;
+    const INFINITY: Self;
+    const NEG_INFINITY: Self;
+
+    fn from_f64(x: f64) -> Self;
+    fn from_usize(x: usize) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn zero() -> Self { Self::default() }
+    fn one() -> Self { Self::from_f64(1.0) }
+
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn log10(self) -> Self;
+    fn log2(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn is_finite(self) -> bool;
+    fn is_nan(self) -> bool;
+    fn total_cmp(&self, other: &Self) -> ::std::cmp::Ordering;
+}
+
+macro_rules! __float_impl {
+    ( $( $t:ident ),+ ) => {$(
+        impl Float for $t {
+            const PI: Self = ::std::$t::consts::PI;
+            const EPSILON: Self = $t::EPSILON;
+            const INFINITY: Self = $t::INFINITY;
+            const NEG_INFINITY: Self = $t::NEG_INFINITY;
+
+            fn from_f64(x: f64) -> Self { x as $t }
+            fn from_usize(x: usize) -> Self { x as $t }
+            fn to_f64(self) -> f64 { self as f64 }
+
+            __delegate!($t; abs, sqrt, exp, ln, log10, log2, sin, cos, tan, asin, acos, atan,
+                sinh, cosh, floor, ceil, round);
+            __delegate!($t; (atan2, Self), (hypot, Self), (powf, Self), (max, Self), (min, Self),
+                (powi, i32));
+
+            fn is_finite(self) -> bool { $t::is_finite(self) }
+            fn is_nan(self) -> bool { $t::is_nan(self) }
+            fn total_cmp(&self, other: &Self) -> ::std::cmp::Ordering { $t::total_cmp(self, other) }
+        }
+    )+}
+}
+
+macro_rules! __delegate {
+    ( $t:ident; $( $f:ident ),+ ) => {
+        $( fn $f(self) -> Self { $t::$f(self) } )+
+    };
+    ( $t:ident; $( ($f:ident, $a:ty) ),+ ) => {
+        $( fn $f(self, other: $a) -> Self { $t::$f(self, other) } )+
+    };
+}
+
+__float_impl!(f32, f64);
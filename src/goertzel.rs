@@ -0,0 +1,69 @@
+use complex::Complex;
+use float::Float;
+
+fn resonate<T: Float>(src: &[T], coeff: T) -> (T, T) {
+    src.iter().fold((T::zero(), T::zero()), |(s1, s2), &x| (x + coeff * s1 - s2, s1))
+}
+
+/// Single DFT bin `X(bin)` of `src`, identical to `dft(src)[bin]`.
+pub fn goertzel<T: Float>(src: &[T], bin: usize) -> Complex<T> {
+    goertzel_generalized(src, T::from_usize(bin))
+}
+
+/// DTFT of `src` at `bin` cycles per `src.len()` samples, where `bin` need not be an integer.
+pub fn goertzel_generalized<T: Float>(src: &[T], bin: T) -> Complex<T> {
+    let w = T::from_f64(2.0) * T::PI * bin / T::from_usize(src.len());
+    let (s1, s2) = resonate(src, T::from_f64(2.0) * w.cos());
+    let y = Complex::new(T::from_f64(2.0) * w.cos() * s1 - s2, T::zero())
+        - Complex::from_polar(T::one(), -w) * s1;
+
+    y * Complex::from_polar(T::one(), -w * T::from_usize(src.len()))
+}
+
+/// Squared magnitude at `freq` Hz without the final phase correction, which is all a
+/// detector needs.
+pub fn goertzel_power<T: Float>(src: &[T], freq: T, sample_rate: u32) -> T {
+    let w = T::from_f64(2.0) * T::PI * freq / T::from_f64(sample_rate as f64);
+    let coeff = T::from_f64(2.0) * w.cos();
+    let (s1, s2) = resonate(src, coeff);
+    s1 * s1 + s2 * s2 - coeff * s1 * s2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ goertzel, goertzel_generalized, goertzel_power };
+    use complex::Complex;
+    use dft::dft;
+    use synth::sine;
+
+    #[test]
+    fn test_goertzel_matches_dft() {
+        let src: Vec<f64> = (0..50).map(|i| (i as f64 * 0.3).sin() + (i as f64 * 1.9).cos() * 0.5).collect();
+        let reference = dft(src.clone());
+
+        for (k, &x) in reference.iter().enumerate() {
+            assert!((goertzel(&src, k) - x).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_goertzel_generalized() {
+        let (src, bin) = (sine(1.0f64, 1.0, 40, 40), 3.25f64);
+        let expected = src.iter().enumerate().fold(Complex::default(), |acc, (n, &x)| {
+            acc + Complex::from_polar(x, -2.0 * ::std::f64::consts::PI * bin * n as f64 / 40.0)
+        });
+
+        assert!((goertzel_generalized(&src, bin) - expected).norm() < 1e-10);
+    }
+
+    #[test]
+    fn test_goertzel_power() {
+        let (rate, len) = (8000, 800);
+        let src = sine(770.0f64, 0.5, rate, len);
+        let on = goertzel_power(&src, 770.0, rate);
+        let off = goertzel_power(&src, 852.0, rate);
+
+        assert!((on.sqrt() - 0.5 * len as f64 / 2.0).abs() < 1e-6);
+        assert!(off < on * 1e-2);
+    }
+}
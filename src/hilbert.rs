@@ -0,0 +1,159 @@
+use complex::Complex;
+use czt::{ fft_any, ifft_any };
+use float::Float;
+use unwrap_phase;
+
+/// Analytic signal `x + j H{x}` computed by zeroing the negative frequencies of the
+/// whole signal.
+pub fn analytic_signal<T: Float>(src: &[T]) -> Vec<Complex<T>> {
+    let len = src.len();
+    let spectrum = fft_any(src.iter().map(|&x| Complex::new(x, T::zero())).collect());
+    let (two, nyquist) = (T::from_f64(2.0), len.div_ceil(2));
+
+    ifft_any(spectrum.into_iter().enumerate().map(|(k, c)| match k {
+        0 => c,
+        k if 2 * k == len => c,
+        k if k < nyquist => c * two,
+        _ => Complex::default()
+    }).collect())
+}
+
+pub fn hilbert<T: Float>(src: &[T]) -> Vec<T> {
+    analytic_signal(src).into_iter().map(|c| c.im).collect()
+}
+
+/// Windowed type III FIR Hilbert transformer with `num + 1` taps, `num` even, in the
+/// same shape as `fir_lpf`.
+pub fn hilbert_fir<T: Float>(num: isize, src: Vec<T>) -> Vec<T> {
+    assert!(num % 2 == 0, "hilbert_fir needs an even order, got {}", num);
+    (0..(num + 1)).map(|i| match i - num / 2 {
+        n if n % 2 == 0 => T::zero(),
+        n => T::from_f64(2.0) / (T::PI * T::from_f64(n as f64))
+    }).zip(src.iter()).map(|(b, &w)| b * w).collect()
+}
+
+/// Streaming analytic signal. The real part is the input delayed by the filter's group
+/// delay so that both parts stay aligned.
+pub struct HilbertFilter<T> {
+    taps: Vec<T>,
+    history: Vec<T>
+}
+
+impl<T: Float> HilbertFilter<T> {
+    pub fn new(taps: Vec<T>) -> Self {
+        assert!(taps.len() % 2 == 1, "HilbertFilter needs an odd number of taps, got {}", taps.len());
+        let history = vec![T::zero(); taps.len() - 1];
+        HilbertFilter { taps, history }
+    }
+
+    pub fn delay(&self) -> usize { (self.taps.len() - 1) / 2 }
+
+    pub fn process(&mut self, block: &[T]) -> Vec<Complex<T>> {
+        let (order, delay) = (self.taps.len() - 1, self.delay());
+        let mut padded = ::std::mem::take(&mut self.history);
+        padded.extend_from_slice(block);
+
+        let mut imag = vec![T::zero(); block.len()];
+        T::fir(&self.taps, &padded, &mut imag);
+
+        let res = imag.into_iter().enumerate()
+            .map(|(i, im)| Complex::new(padded[i + order - delay], im)).collect();
+        self.history = padded[padded.len() - order..].to_vec();
+        res
+    }
+}
+
+pub fn instantaneous_amplitude<T: Float>(analytic: &[Complex<T>]) -> Vec<T> {
+    analytic.iter().map(Complex::norm).collect()
+}
+
+pub fn instantaneous_phase<T: Float>(analytic: &[Complex<T>]) -> Vec<T> {
+    unwrap_phase(&analytic.iter().map(Complex::arg).collect::<Vec<_>>())
+}
+
+/// Frequency in Hz between consecutive samples, so the result is one shorter than the
+/// input.
+pub fn instantaneous_frequency<T: Float>(analytic: &[Complex<T>], sample_rate: u32) -> Vec<T> {
+    let scale = T::from_f64(sample_rate as f64) / (T::from_f64(2.0) * T::PI);
+    analytic.windows(2).map(|w| (w[1] * w[0].conj()).arg() * scale).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ HilbertFilter, analytic_signal, hilbert, hilbert_fir, instantaneous_amplitude,
+        instantaneous_frequency, instantaneous_phase };
+    use synth::{ chirp, sine };
+    use std::f64::consts::PI;
+    use window::{ Symmetry, blackman };
+
+    const RATE: u32 = 44100;
+
+    #[test]
+    fn test_hilbert_of_cosine() {
+        let len = 256;
+        let src: Vec<f64> = (0..len).map(|n| (2.0 * PI * 8.0 * n as f64 / len as f64).cos()).collect();
+        let expected: Vec<f64> = (0..len).map(|n| (2.0 * PI * 8.0 * n as f64 / len as f64).sin()).collect();
+
+        for (x, y) in hilbert(&src).iter().zip(expected.iter()) {
+            assert!((x - y).abs() < 1e-9);
+        }
+        for (x, y) in analytic_signal(&src[..255]).iter().zip(src.iter()) {
+            assert!((x.re - y).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_chirp_frequency_tracking() {
+        let (start, end, len) = (1500.0, 2500.0, RATE as usize / 5);
+        let src = chirp(start, end, 0.5, RATE, len);
+        let freqs = instantaneous_frequency(&analytic_signal(&src), RATE);
+
+        for i in (len / 10..len * 9 / 10).step_by(97) {
+            let expected = start + (end - start) * (i as f64 + 0.5) / len as f64;
+            assert!((freqs[i] - expected).abs() < 2.0, "{}: {} != {}", i, freqs[i], expected);
+        }
+    }
+
+    #[test]
+    fn test_envelope_and_phase() {
+        let len = 4410;
+        let envelope: Vec<f64> = (0..len).map(|i| 0.5 + 0.4 * (2.0 * PI * 5.0 * i as f64 / RATE as f64).sin()).collect();
+        let carrier = sine(1000.0, 1.0, RATE, len);
+        let src: Vec<_> = envelope.iter().zip(carrier.iter()).map(|(a, c)| a * c).collect();
+        let analytic = analytic_signal(&src);
+
+        let amps = instantaneous_amplitude(&analytic);
+        for i in (len / 10..len * 9 / 10).step_by(50) {
+            assert!((amps[i] - envelope[i]).abs() < 1e-2);
+        }
+
+        let phase = instantaneous_phase(&analytic);
+        let slope = (phase[3000] - phase[1000]) / 2000.0;
+        assert!((slope - 2.0 * PI * 1000.0 / RATE as f64).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_streaming_fir() {
+        let (start, end, len) = (1500.0, 2500.0, RATE as usize / 5);
+        let src = chirp(start, end, 0.5, RATE, len);
+        let taps = hilbert_fir(100, blackman(101, Symmetry::Symmetric));
+
+        let whole = HilbertFilter::new(taps.clone()).process(&src);
+        let mut filter = HilbertFilter::new(taps);
+        let blocks: Vec<_> = src.chunks(333).flat_map(|b| filter.process(b)).collect();
+        assert!(whole.iter().zip(blocks.iter()).all(|(x, y)| (*x - *y).norm() < 1e-12));
+
+        let delay = filter.delay();
+        let freqs = instantaneous_frequency(&blocks, RATE);
+        for i in (len / 10..len * 9 / 10).step_by(97) {
+            let expected = start + (end - start) * (i as f64 - delay as f64 + 0.5) / len as f64;
+            assert!((freqs[i] - expected).abs() < 5.0, "{}: {} != {}", i, freqs[i], expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "odd number of taps")]
+    fn test_filter_without_taps() {
+        HilbertFilter::<f64>::new(Vec::new());
+    }
+}
@@ -1,12 +1,64 @@
 extern crate byteorder;
 extern crate rayon;
 use std::fmt;
-use std::fs::File; use std::f32::consts::PI;
+use std::fs::File;
 use std::io::prelude::*;
 use std::io::Cursor; 
 use byteorder::{ LittleEndian, ReadBytesExt }; 
 use rayon::prelude::*;
 
+pub mod cepstrum;
+pub mod chroma;
+pub mod complex;
+pub mod cqt;
+pub mod czt;
+pub mod dct;
+pub mod descriptors;
+pub mod distortion;
+pub mod dft;
+pub mod dtmf;
+pub mod fir;
+pub mod float;
+pub mod goertzel;
+pub mod hilbert;
+pub mod loudness;
+pub mod mel;
+pub mod onset;
+pub mod pitch;
+pub mod psd;
+pub mod simd;
+pub mod spectrogram;
+pub mod stft;
+pub mod sweep;
+pub mod synth;
+pub mod window;
+pub mod xcorr;
+pub use cepstrum::{
+    Lifter, cepstral_pitch, complex_cepstrum, inverse_complex_cepstrum, lifter, power_cepstrum, real_cepstrum,
+    spectral_envelope
+};
+pub use chroma::{ Chroma, Chromagram, Key, Mode, estimate_key };
+pub use complex::Complex;
+pub use cqt::ConstantQ;
+pub use czt::{ czt, czt_complex, czt_frequencies, fft_any, ifft_any };
+pub use descriptors::{ DescriptorExtractor, Descriptors };
+pub use distortion::{ Distortion, DistortionAnalyzer, Harmonic };
+pub use dft::{ dft, dft_complex, idft };
+pub use fir::{ FirDesign, FirWindow, KaiserDesign, KaiserFilter, Response, frequency_response, kaiser_beta };
+pub use float::Float;
+pub use goertzel::{ goertzel, goertzel_generalized, goertzel_power };
+pub use dtmf::{ DtmfDecoder, DtmfDigit, decode_dtmf };
+pub use loudness::{ Biquad, Loudness, LoudnessMeter, channel_weights, k_weighting };
+pub use mel::{ MelFilterbank, MelScale, Mfcc };
+pub use onset::{ BeatTracker, Beats, OnsetDetector, OnsetFunction, PeakPicker, onset_strength };
+pub use pitch::{ Pitch, PitchDetector, PitchMethod };
+pub use psd::{ Averaging, Psd, Welch };
+pub use spectrogram::{ Colormap, FrequencyScale, Image, Renderer, Spectrogram };
+pub use stft::{ Istft, Stft };
+pub use sweep::Sweep;
+pub use window::{ Symmetry, WindowMetrics };
+pub use xcorr::{ Delay, DelayEstimator, Weighting, autocorrelation, cross_correlation, gcc_phat };
+
 #[macro_export] 
 macro_rules! prepare_default_pcm { 
     ($pcm:ident) => { 
@@ -25,7 +77,7 @@ macro_rules! genbufs {
     ( $t:ty, $($size:expr),+ ) => { ( $(vec![0 as $t; $size],)+ ) } 
 } 
 
-const VALIDATION_ERR: &'static str = "an invalid sized vector exists.";
+const VALIDATION_ERR: &str = "an invalid sized vector exists.";
 
 trait Validator {
     fn validate(&self) -> Result<(), &str>; 
@@ -164,10 +216,6 @@ fn u8vec_to_u16_le(src: Vec<u8>) -> u16 {
     reader.read_u16::<LittleEndian>().unwrap() 
 }
 
-unsafe fn from_bytes<'a, T>(buf: &'a [u8]) -> &'a [T] {
-    std::slice::from_raw_parts(buf.as_ptr() as *const T, buf.len() / std::mem::size_of::<T>()) 
-}
-
 type FormatHeader = SubcHeader;
 type DataHeader = SubcHeader;
 
@@ -194,6 +242,38 @@ impl Wave {
         }
     }
 
+    pub fn from_samples(data: Vec<f32>, sample_rate: u32) -> Self {
+        Wave::from_interleaved(data, 1, sample_rate)
+    }
+
+    /// Interleaves equally long channels into one wave.
+    pub fn from_channels(channels: &[Vec<f32>], sample_rate: u32) -> Self {
+        let len = channels.first().map_or(0, Vec::len);
+        assert!(channels.iter().all(|c| c.len() == len), "channels differ in length");
+        let data = (0..len).flat_map(|i| channels.iter().map(move |c| c[i])).collect();
+        Wave::from_interleaved(data, channels.len() as u16, sample_rate)
+    }
+
+    fn from_interleaved(data: Vec<f32>, channels: u16, sample_rate: u32) -> Self {
+        let (data_size, block_align) = (data.len() as u32 * 2, channels * 2);
+        Wave::new(
+            Riff::with_valid(b"RIFF".to_vec(), 36 + data_size, b"WAVE".to_vec()),
+            SubcHeader::with_valid(b"fmt ".to_vec(), 16),
+            Format::new(1, channels, sample_rate, sample_rate * block_align as u32, block_align, 16),
+            SubcHeader::with_valid(b"data".to_vec(), data_size),
+            data
+        )
+    }
+
+    /// De-interleaved samples of each channel.
+    pub fn channels(&self) -> Vec<Vec<f32>> {
+        let count = self.format.channels.max(1) as usize;
+        (0..count).map(|c| self.data.iter().skip(c).step_by(count).cloned().collect()).collect()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.data.len() as f32 / self.format.channels.max(1) as f32 / self.format.sample_rate as f32
+    }
 }
 
 pub fn read_wave_mono16(fname: &str) -> Wave {
@@ -201,9 +281,7 @@ pub fn read_wave_mono16(fname: &str) -> Wave {
     let (riff, format_header, format, data_header) = __from_file!(&mut file, Riff, SubcHeader, Format, SubcHeader);
 
     __read_file!(&mut file, (tmp, data_header.size as usize));
-    let data: Vec<_> = unsafe { 
-        from_bytes::<u16>(tmp.as_slice()).into_par_iter().map(|&c| c as f32 / 32768.0).collect()
-    };
+    let data: Vec<_> = tmp.par_chunks_exact(2).map(|c| i16::from_le_bytes([c[0], c[1]]) as f32 / 32768.0).collect();
 
     Wave::new(
         riff, 
@@ -214,139 +292,143 @@ pub fn read_wave_mono16(fname: &str) -> Wave {
     )
 }
 
-pub fn hann(n: usize) -> Vec<f32> {
-    (0..n).into_iter().map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * match i {
-        i if i % 2 == 0 => i as f32,
-        _ => i as f32 + 0.5
-    } / n as f32).cos()).collect() 
+/// Symmetric Hann window, as used for FIR design. See `window` for other windows and
+/// the periodic variant.
+#[deprecated(note = "use `window::hann(n, Symmetry::Symmetric)`, which makes the symmetry explicit")]
+pub fn hann<T: Float>(n: usize) -> Vec<T> {
+    window::hann(n, window::Symmetry::Symmetric)
 }
 
-fn count_stage(n: usize) -> usize { (n as f32).log(2.0) as usize }
+fn count_stage(n: usize) -> usize {
+    assert!(n.is_power_of_two(), "fft length must be a power of two, got {}", n);
+    n.trailing_zeros() as usize
+}
 
-fn butterfly_params_helper(curr: usize, limit: usize, j: usize, m: usize) -> f32 {
-   let (n, r) = (
-       2usize.pow((limit - curr) as u32) + m,
-       2usize.pow((curr - 1) as u32) * j
-   );
-   2.0 * PI * r as f32 / n as f32
+fn butterfly_params_helper<T: Float>(curr: usize, limit: usize, j: usize) -> T {
+    T::from_f64(2.0) * T::PI * T::from_usize(j) / T::from_usize(2usize.pow((limit - curr + 1) as u32))
 }
 
-fn fft_butterfly_params(curr: usize, limit: usize, j: usize, m: usize) -> (f32, f32) {
-    let w = butterfly_params_helper(curr, limit, j, m);
-    (w.cos(), -w.sin())
+fn fft_butterfly_params<T: Float>(curr: usize, limit: usize, j: usize) -> Complex<T> {
+    Complex::from_polar(T::one(), -butterfly_params_helper::<T>(curr, limit, j))
 }
 
-fn ifft_butterfly_params(curr: usize, limit: usize, j: usize, m: usize) -> (f32, f32) {
-    let w = butterfly_params_helper(curr, limit, j, m);
-    (w.cos(), w.sin())
+fn ifft_butterfly_params<T: Float>(curr: usize, limit: usize, j: usize) -> Complex<T> {
+    Complex::from_polar(T::one(), butterfly_params_helper(curr, limit, j))
 }
 
-fn compute_stage<F>(
-    src: Vec<(f32, f32)>, 
+fn compute_stage<T, F>(
+    mut src: Vec<Complex<T>>, 
     curr: usize, 
     limit: usize, 
     butterfly_params_func: F
-) -> Vec<(f32, f32)> where F: Fn(usize, usize, usize, usize) -> (f32, f32) {
+) -> Vec<Complex<T>> where T: Float, F: Fn(usize, usize, usize) -> Complex<T> {
     match curr {
-        curr if curr >= limit => src,
-        _ => compute_stage(match curr - 1 {
-            0 => src,
-            num => (0..(2usize.pow(num as u32))).into_iter().fold(Vec::new(), |mut acc, i| {
-                let res: Vec<_> = (0..(2usize.pow((limit - curr) as u32))).into_iter().map(|j| {
-                    let m = 2usize.pow((limit - curr + 1) as u32) * i + j;
-                    let n = 2usize.pow((limit - curr) as u32) + m;
-
-                    let ((a_real, a_img), (b_real, b_img), (c_real, c_img)) = (
-                        src[m], 
-                        src[n], 
-                        butterfly_params_func(curr, limit, j, m)
-                    );
-
-                    if curr == limit {
-                        (
-                            (a_real + b_real, a_img + b_img), 
-                            (a_real - b_real, a_img - b_img)
-                        )
-                    } else { 
-                        (
-                            (a_real + b_real, a_img + b_img),
-                            (
-                                (a_real - b_real) * c_real  - (a_img - b_img) * c_img,
-                                (a_img - b_img) * c_real  - (a_real - b_real) * c_img
-                            )
-                        )
-                    }
-                }).collect();
-
-                let (mut next, mut front, mut back) = (Vec::new(), 
-                    res.clone().into_iter().map(|(i, _)| i).collect::<Vec<_>>(), 
-                    res.into_iter().map(|(_, j)| j).collect::<Vec<_>>()
-                );
-
-                next.append(&mut front); next.append(&mut back);
-                acc.append(&mut next); acc
-            })
-        }, curr + 1, limit, butterfly_params_func)
+        curr if curr > limit => src,
+        _ => {
+            let half = 2usize.pow((limit - curr) as u32);
+            let twiddles: Vec<_> = (0..half).map(|j| butterfly_params_func(curr, limit, j)).collect();
+
+            for block in src.chunks_mut(2 * half) {
+                let (front, back) = block.split_at_mut(half);
+                T::butterfly(front, back, &twiddles);
+            }
+            compute_stage(src, curr + 1, limit, butterfly_params_func)
+        }
     }
 }
 
 fn indices(len: usize) -> Vec<usize> {
-    (0..len).into_iter().map(compute_index_weight).collect()
+    let bits = count_stage(len);
+    (0..len).map(|idx| compute_index_weight(idx, bits)).collect()
 }
 
-fn compute_index_weight(idx: usize) -> usize {
-    let num = match idx {
-        0 => 0,
-        _ => (idx as f32).log(2.0) as usize
-    };
-    (0..num).into_iter().fold(0, |acc, i| acc + 2usize.pow(i as u32))
+fn compute_index_weight(idx: usize, bits: usize) -> usize {
+    (0..bits).fold(0, |acc, i| acc | ((idx >> i) & 1) << (bits - 1 - i))
 }
 
-fn reverse_bits<'a>(v: &'a mut Vec<(f32, f32)>) {
+fn reverse_bits<T>(v: &mut [T]) {
     for (i, j) in indices(v.len()).iter().enumerate().filter(|&(i, j)| i < *j) { 
         v.swap(i, *j); 
     }
 }
  
-pub fn fft(src: Vec<f32>) -> Vec<(f32, f32)> {  
-    let (stage_num, pair_v) = (
-        count_stage(src.len()),
-        src.into_iter().map(|i| (i, 0.0)).collect()
-    );
+pub fn fft<T: Float>(src: Vec<T>) -> Vec<Complex<T>> {  
+    fft_complex(src.into_iter().map(|i| Complex::new(i, T::zero())).collect())
+}
 
-    let mut res = compute_stage(pair_v, 1, stage_num, fft_butterfly_params);
+pub fn fft_complex<T: Float>(src: Vec<Complex<T>>) -> Vec<Complex<T>> {  
+    let stage_num = count_stage(src.len());
+    let mut res = compute_stage(src, 1, stage_num, fft_butterfly_params);
     reverse_bits(&mut res); res
 }
 
-fn sinc(x: f32) -> f32 {
+pub fn magnitude_spectrum<T: Float>(src: &[Complex<T>]) -> Vec<T> {
+    src.iter().map(Complex::norm).collect()
+}
+
+pub fn phase_spectrum<T: Float>(src: &[Complex<T>]) -> Vec<T> {
+    src.iter().map(Complex::arg).collect()
+}
+
+pub fn db_spectrum<T: Float>(src: &[Complex<T>]) -> Vec<T> {
+    src.iter().map(Complex::db).collect()
+}
+
+/// Removes the `2 pi` jumps from a wrapped phase sequence.
+pub fn unwrap_phase<T: Float>(src: &[T]) -> Vec<T> {
+    let two_pi = T::from_f64(2.0) * T::PI;
+    let mut offset = T::zero();
+    src.iter().enumerate().map(|(i, &p)| {
+        if i > 0 {
+            let delta = p - src[i - 1];
+            offset -= two_pi * ((delta + T::PI) / two_pi).floor();
+        }
+        p + offset
+    }).collect()
+}
+
+fn sinc<T: Float>(x: T) -> T {
     match x {
-        x if x == 0.0 => 1.0,
+        x if x == T::zero() => T::one(),
         _ => x.sin() / x
     }
 }
 
-pub fn fir_lpf(freq: f32, num: isize, src: Vec<f32>) -> Vec<f32> {
-    (0..(num + 1)).into_iter().map(|i| 2.0 * freq * sinc(2.0 * PI * freq * (i - num / 2) as f32))
-        .zip(src.iter()).map(|(b, w)| b * w).collect()
+pub fn fir_lpf<T: Float>(freq: T, num: isize, src: Vec<T>) -> Vec<T> {
+    let two = T::from_f64(2.0);
+    (0..(num + 1)).map(|i| two * freq * sinc(two * T::PI * freq * T::from_f64((i - num / 2) as f64)))
+        .zip(src.iter()).map(|(b, &w)| b * w).collect()
+}
+
+pub fn complex_mul<T: Float>(src: Vec<Complex<T>>, filter: &[Complex<T>]) -> Vec<Complex<T>> {
+    let mut dest = src;
+    T::complex_mul(&mut dest, filter); dest
+}
+
+pub fn apply_fir<T: Float>(filter: &[T], src: &[T]) -> Vec<T> {
+    let (mut padded, mut dest) = (vec![T::zero(); filter.len() - 1], vec![T::zero(); src.len()]);
+    padded.extend_from_slice(src);
+    T::fir(filter, &padded, &mut dest); dest
 }
 
-fn bilinear_transform(anal_freq: f32) -> f32 {
-    (PI * anal_freq).tan() / (2.0 * PI) 
+fn bilinear_transform<T: Float>(anal_freq: T) -> T {
+    (T::PI * anal_freq).tan() / (T::from_f64(2.0) * T::PI) 
 }
 
-type IIRDenominatorParams = (f32, f32, f32);
-type IIRNumeratorParams = (f32, f32, f32);
+type IIRDenominatorParams<T> = (T, T, T);
+type IIRNumeratorParams<T> = (T, T, T);
 
-pub fn iir_lpf(anal_freq: f32, qf: f32) -> (IIRDenominatorParams, IIRNumeratorParams) {
+pub fn iir_lpf<T: Float>(anal_freq: T, qf: T) -> (IIRDenominatorParams<T>, IIRNumeratorParams<T>) {
+    let (one, two) = (T::one(), T::from_f64(2.0));
     let digit_freq = bilinear_transform(anal_freq);
-    let temp = 4.0 * PI.powi(2) * digit_freq.powi(2);
-    let denom = 1.0 + 2.0 * PI * digit_freq / qf + temp;
+    let temp = T::from_f64(4.0) * T::PI.powi(2) * digit_freq.powi(2);
+    let denom = one + two * T::PI * digit_freq / qf + temp;
 
-    ((1.0,
-      (2.0 * temp - 2.0) / denom,
-      (1.0 - 2.0 * PI * digit_freq / qf + temp / denom)),
+    ((one,
+      (two * temp - two) / denom,
+      (one - two * T::PI * digit_freq / qf + temp) / denom),
      (temp / denom,
-      2.0 * temp / denom, temp / denom)) 
+      two * temp / denom, temp / denom)) 
 } 
 
 pub trait Trigram<'a, T: 'a + fmt::Debug + Clone>: Iterator<Item=T> where Self: Sized {
@@ -360,9 +442,9 @@ impl<'a, T: 'a + fmt::Debug + Clone, U: 'a + Iterator<Item=T> + Clone> Trigram<'
 }
 
 pub struct Trigrams<'a, T: 'a + fmt::Debug + Clone> {
-    first: Box<Iterator<Item = T> + 'a>,
-    second: Box<Iterator<Item = T> + 'a>,
-    third: Box<Iterator<Item = T> + 'a>,
+    first: Box<dyn Iterator<Item = T> + 'a>,
+    second: Box<dyn Iterator<Item = T> + 'a>,
+    third: Box<dyn Iterator<Item = T> + 'a>,
     remaining: usize,
     pad: T
 }
@@ -417,9 +499,20 @@ impl <'a, T: 'a + fmt::Debug + Clone> Iterator for Trigrams<'a, T> {
     }
 }
 
+pub fn ifft<T: Float>(src: Vec<Complex<T>>) -> Vec<Complex<T>> {
+    let (n, stage_num) = (
+        src.len(), 
+        count_stage(src.len())
+    );
+
+    let mut res = compute_stage(src, 1, stage_num, ifft_butterfly_params);
+    reverse_bits(&mut res); 
+    res.into_iter().map(|c| c / T::from_usize(n)).collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Trigram;
+    use super::{ Complex, Float, Trigram, Wave, apply_fir, fft, ifft, iir_lpf, read_wave_mono16 };
 
     #[test]
     fn test_trigram() {
@@ -433,15 +526,107 @@ mod tests {
             (&3, &4, &5)]
         );
     }
-}
 
-pub fn ifft(src: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
-    let (n, stage_num) = (
-        src.len(), 
-        count_stage(src.len())
-    );
+    #[test]
+    fn test_read_wave_mono16() {
+        let wave = read_wave_mono16("examples/resources/sine_500hz.wav");
+        assert_eq!((wave.format.sample_rate, wave.data.len()), (8000, 8000));
+        assert_eq!(&wave.data[..5], &[0.0, 6270.0 / 32768.0, 11585.0 / 32768.0, 15137.0 / 32768.0, 0.5]);
+        assert_eq!(wave.data[12], -0.5);
+    }
 
-    let mut res = compute_stage(src, 1, stage_num, ifft_butterfly_params);
-    reverse_bits(&mut res); 
-    res.into_iter().map(|(r, i)| (r / n as f32, i / n as f32)).collect()
+    #[test]
+    fn test_read_wave_mono16_odd_data() {
+        // Samples are signed little-endian; a dangling last byte is dropped.
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&41u32.to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        for &(value, size) in &[(1u32, 2), (1, 2), (8000, 4), (16000, 4), (2, 2), (16, 2)] {
+            bytes.extend_from_slice(&value.to_le_bytes()[..size]);
+        }
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&5u32.to_le_bytes());
+        bytes.extend_from_slice(&[0x00, 0x40, 0x00, 0xc0, 0x7f]);
+
+        let path = ::std::env::temp_dir().join(format!("odd_data_{}.wav", ::std::process::id()));
+        ::std::fs::write(&path, &bytes).unwrap();
+        let wave = read_wave_mono16(path.to_str().unwrap());
+        ::std::fs::remove_file(&path).unwrap();
+        assert_eq!(wave.data, vec![0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_wave_channels() {
+        let channels = vec![vec![0.1, 0.2, 0.3], vec![-0.1, -0.2, -0.3]];
+        let wave = Wave::from_channels(&channels, 4);
+        assert_eq!(wave.data, vec![0.1, -0.1, 0.2, -0.2, 0.3, -0.3]);
+        assert_eq!((wave.format.channels, wave.format.block_align, wave.format.bit_rate), (2, 4, 16));
+        assert_eq!(wave.duration(), 0.75);
+        assert_eq!(wave.channels(), channels);
+        assert_eq!(Wave::from_samples(vec![0.5; 8], 4).channels(), vec![vec![0.5; 8]]);
+    }
+
+    #[test]
+    fn test_fft() {
+        let res = fft(vec![1.0, 2.0, 3.0, 4.0, 0.0, 0.0, 0.0, 0.0]);
+        let expected = vec![
+            (10.0, 0.0), (-0.414_213_57, -7.242_641), (-2.0, 2.0), (2.414_213_6, -1.242_640_7),
+            (-2.0, 0.0), (2.414_213_6, 1.242_640_7), (-2.0, -2.0), (-0.414_213_57, 7.242_641)
+        ];
+
+        for (x, y) in res.iter().zip(expected.into_iter().map(Complex::from)) {
+            assert!((*x - y).norm() < 1e-5, "{:?} != {:?}", x, y);
+        }
+    }
+
+    #[test]
+    fn test_ifft_round_trip() {
+        let src: Vec<_> = (0..16).map(|i| (i as f32 * 0.7).sin()).collect();
+        let res = ifft(fft(src.clone()));
+
+        for (x, &y) in res.iter().zip(src.iter()) {
+            assert!((x.re - y).abs() < 1e-5 && x.im.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_apply_fir() {
+        let (filter, src) = (vec![0.5, 0.25, -0.125], vec![1.0, 0.0, 2.0, -1.0, 0.0, 0.0, 0.0, 0.0, 3.0]);
+        let expected: Vec<f32> = (0..src.len()).map(|i| filter.iter().enumerate()
+            .filter(|&(j, _)| i >= j).map(|(j, &h)| h * src[i - j]).sum()).collect();
+
+        assert_eq!(apply_fir(&filter, &src), expected);
+    }
+
+    fn round_trip_error<T: Float>(len: usize) -> f64 {
+        let src: Vec<T> = (0..len).map(|i| T::from_f64((i as f64 * 0.37).sin() + (i as f64 * 1.3).cos())).collect();
+        ifft(fft(src.clone())).iter().zip(src.iter())
+            .map(|(x, &y)| (*x - Complex::new(y, T::zero())).norm().to_f64())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_fft_precision() {
+        let (err32, err64) = (round_trip_error::<f32>(4096), round_trip_error::<f64>(4096));
+
+        assert!(err32 < 1e-4);
+        assert!(err64 < 1e-12);
+        assert!(err64 < err32 * 1e-6);
+    }
+
+    fn dc_gain<T: Float>(anal_freq: f64) -> f64 {
+        let (d, n) = iir_lpf(T::from_f64(anal_freq), T::from_f64(0.5f64.sqrt()));
+        (n.0 + n.1 + n.2).to_f64() / (d.0 + d.1 + d.2).to_f64()
+    }
+
+    #[test]
+    fn test_iir_lpf_precision() {
+        assert!((dc_gain::<f64>(0.1) - 1.0).abs() < 1e-12);
+        assert!((dc_gain::<f32>(0.1) - 1.0).abs() < 1e-5);
+
+        let (err32, err64) = ((dc_gain::<f32>(1e-4) - 1.0).abs(), (dc_gain::<f64>(1e-4) - 1.0).abs());
+        assert!(err64 < 1e-6);
+        assert!(err64 < err32);
+    }
 }
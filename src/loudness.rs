@@ -0,0 +1,330 @@
+use window::{ Symmetry, kaiser };
+use Wave;
+
+/// Second order IIR section with `a[0] = 1`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Biquad {
+    pub b: [f64; 3],
+    pub a: [f64; 3]
+}
+
+impl Biquad {
+    pub fn filter(&self, src: &[f64]) -> Vec<f64> {
+        let (b, a) = (self.b, self.a);
+        src.iter().scan((0.0, 0.0), |state, &x| {
+            let y = b[0] * x + state.0;
+            *state = (b[1] * x - a[1] * y + state.1, b[2] * x - a[2] * y);
+            Some(y)
+        }).collect()
+    }
+}
+
+/// The two BS.1770 K-weighting stages: a +4 dB high shelf for the acoustic effect of
+/// the head, then the RLB high-pass. They are derived from their analog prototypes so
+/// any sample rate works, and match the coefficients in the standard at 48 kHz.
+pub fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let warp = |f0: f64| (::std::f64::consts::PI * f0 / sample_rate as f64).tan();
+
+    let (k, q) = (warp(1681.974450955533), 0.7071752369554196);
+    let vh = 10f64.powf(3.999843853973347 / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]
+    };
+
+    let (k, q) = (warp(38.13547087602444), 0.5003270373238773);
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad { b: [1.0, -2.0, 1.0], a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0] };
+
+    [shelf, high_pass]
+}
+
+/// BS.1770 channel weights for the usual layouts: 1 for front channels, 1.41 for the
+/// surrounds of 5.0 (L R C Ls Rs) and 5.1 (L R C LFE Ls Rs), whose LFE is left out.
+pub fn channel_weights(channels: usize) -> Vec<f64> {
+    match channels {
+        5 => vec![1.0, 1.0, 1.0, 1.41, 1.41],
+        6 => vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41],
+        n => vec![1.0; n]
+    }
+}
+
+/// Loudness in LUFS, loudness range in LU and true peak in dBTP. Momentary and
+/// short-term values are given every 100 ms once their window is full.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Loudness {
+    pub integrated: f64,
+    pub range: f64,
+    pub true_peak: f64,
+    pub momentary: Vec<f64>,
+    pub short_term: Vec<f64>
+}
+
+const ABSOLUTE_GATE: f64 = -70.0;
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 24;
+
+fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Power of the blocks louder than the absolute gate and than `relative` LU below
+/// their mean.
+fn gated(blocks: &[f64], relative: f64) -> Vec<f64> {
+    let loud: Vec<_> = blocks.iter().cloned().filter(|&z| to_lufs(z) > ABSOLUTE_GATE).collect();
+    match loud.len() {
+        0 => loud,
+        _ => {
+            let threshold = to_lufs(mean(&loud)) + relative;
+            loud.into_iter().filter(|&z| to_lufs(z) > threshold).collect()
+        }
+    }
+}
+
+/// EBU R128 meter after ITU-R BS.1770-4, EBU Tech 3341 and 3342.
+#[derive(Clone, Debug)]
+pub struct LoudnessMeter {
+    pub sample_rate: u32,
+    pub weights: Vec<f64>
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        assert!(sample_rate >= 10, "sample rate of {} Hz has no 100 ms segments", sample_rate);
+        LoudnessMeter { sample_rate, weights: channel_weights(channels) }
+    }
+
+    fn segment_len(&self) -> usize {
+        self.sample_rate as usize / 10
+    }
+
+    /// Weighted sum of the K-weighted channel energies in each whole 100 ms segment.
+    fn segments(&self, channels: &[Vec<f32>]) -> Vec<f64> {
+        assert_eq!(channels.len(), self.weights.len(), "expected {} channels", self.weights.len());
+        let (len, filters) = (self.segment_len(), k_weighting(self.sample_rate));
+        let count = channels.first().map_or(0, |c| c.len() / len);
+
+        channels.iter().zip(self.weights.iter()).fold(vec![0.0; count], |mut acc, (channel, &weight)| {
+            let src: Vec<f64> = channel.iter().map(|&x| x as f64).collect();
+            let weighted = filters.iter().fold(src, |x, f| f.filter(&x));
+            for (total, segment) in acc.iter_mut().zip(weighted.chunks(len)) {
+                *total += weight * segment.iter().map(|x| x * x).sum::<f64>();
+            }
+            acc
+        })
+    }
+
+    /// Mean square power of every window of `span` segments.
+    fn blocks(&self, segments: &[f64], span: usize) -> Vec<f64> {
+        let len = (span * self.segment_len()) as f64;
+        segments.windows(span).map(|w| w.iter().sum::<f64>() / len).collect()
+    }
+
+    /// Loudness of 400 ms windows.
+    pub fn momentary(&self, channels: &[Vec<f32>]) -> Vec<f64> {
+        self.blocks(&self.segments(channels), 4).into_iter().map(to_lufs).collect()
+    }
+
+    /// Loudness of 3 s windows.
+    pub fn short_term(&self, channels: &[Vec<f32>]) -> Vec<f64> {
+        self.blocks(&self.segments(channels), 30).into_iter().map(to_lufs).collect()
+    }
+
+    /// Gated loudness over the whole programme, or negative infinity for silence.
+    pub fn integrated(&self, channels: &[Vec<f32>]) -> f64 {
+        self.integrated_from(&self.segments(channels))
+    }
+
+    fn integrated_from(&self, segments: &[f64]) -> f64 {
+        match gated(&self.blocks(segments, 4), -10.0) {
+            ref blocks if blocks.is_empty() => f64::NEG_INFINITY,
+            blocks => to_lufs(mean(&blocks))
+        }
+    }
+
+    /// Spread between the 10th and 95th percentiles of the gated short-term loudness.
+    pub fn range(&self, channels: &[Vec<f32>]) -> f64 {
+        self.range_from(&self.segments(channels))
+    }
+
+    fn range_from(&self, segments: &[f64]) -> f64 {
+        let mut levels: Vec<_> = gated(&self.blocks(segments, 30), -20.0).into_iter().map(to_lufs).collect();
+        levels.sort_by(f64::total_cmp);
+        let percentile = |p: f64| levels[((levels.len() - 1) as f64 * p).round() as usize];
+        match levels.len() {
+            0 => 0.0,
+            _ => percentile(0.95) - percentile(0.1)
+        }
+    }
+
+    /// Highest absolute sample after 4x oversampling, in dBTP.
+    pub fn true_peak(&self, channels: &[Vec<f32>]) -> f64 {
+        let taps = OVERSAMPLING * TAPS_PER_PHASE;
+        let centre = (taps - 1) as f64 / 2.0;
+        let window: Vec<f64> = kaiser(taps, 8.0, Symmetry::Symmetric);
+        let phases: Vec<Vec<f64>> = (0..OVERSAMPLING).map(|p| {
+            let phase: Vec<_> = (0..TAPS_PER_PHASE).map(|k| {
+                let x = ::std::f64::consts::PI * ((p + OVERSAMPLING * k) as f64 - centre) / OVERSAMPLING as f64;
+                window[p + OVERSAMPLING * k] * if x == 0.0 { 1.0 } else { x.sin() / x }
+            }).collect();
+            let gain = phase.iter().sum::<f64>();
+            phase.into_iter().map(|h| h / gain).collect()
+        }).collect();
+
+        let peak = channels.iter().flat_map(|channel| {
+            let phases = &phases;
+            (0..channel.len() + TAPS_PER_PHASE).flat_map(move |i| phases.iter().map(move |phase| {
+                (0..TAPS_PER_PHASE).filter(|&k| k <= i && i - k < channel.len())
+                    .map(|k| phase[k] * channel[i - k] as f64).sum::<f64>().abs()
+            }))
+        }).fold(0.0, f64::max);
+        20.0 * peak.log10()
+    }
+
+    pub fn measure(&self, channels: &[Vec<f32>]) -> Loudness {
+        let segments = self.segments(channels);
+        Loudness {
+            integrated: self.integrated_from(&segments),
+            range: self.range_from(&segments),
+            true_peak: self.true_peak(channels),
+            momentary: self.blocks(&segments, 4).into_iter().map(to_lufs).collect(),
+            short_term: self.blocks(&segments, 30).into_iter().map(to_lufs).collect()
+        }
+    }
+
+    pub fn measure_wave(&self, wave: &Wave) -> Loudness {
+        assert_eq!(wave.format.sample_rate, self.sample_rate, "wave and meter sample rates differ");
+        self.measure(&wave.channels())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ LoudnessMeter, k_weighting };
+    use synth::sine;
+    use Wave;
+
+    const RATE: u32 = 48000;
+
+    /// Stereo 1 kHz sine sections of `(dBFS, seconds)`, as in EBU Tech 3341 and 3342.
+    fn sections(levels: &[(f64, f64)]) -> Vec<Vec<f32>> {
+        let src: Vec<f32> = levels.iter().flat_map(|&(db, secs)| {
+            sine(1000.0, 10f64.powf(db / 20.0), RATE, (secs * RATE as f64).round() as usize)
+        }).map(|x| x as f32).collect();
+        vec![src.clone(), src]
+    }
+
+    #[test]
+    fn test_k_weighting() {
+        let [shelf, high_pass] = k_weighting(RATE);
+        let close = |a: [f64; 3], b: [f64; 3]| a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-8);
+        assert!(close(shelf.b, [1.53512485958697, -2.69169618940638, 1.19839281085285]), "{:?}", shelf);
+        assert!(close(shelf.a, [1.0, -1.69065929318241, 0.73248077421585]), "{:?}", shelf);
+        assert!(close(high_pass.b, [1.0, -2.0, 1.0]));
+        assert!(close(high_pass.a, [1.0, -1.99004745483398, 0.99007225036621]), "{:?}", high_pass);
+    }
+
+    #[test]
+    fn test_integrated() {
+        let meter = LoudnessMeter::new(RATE, 2);
+        let cases: Vec<(Vec<(f64, f64)>, f64)> = vec![
+            (vec![(-23.0, 20.0)], -23.0),
+            (vec![(-33.0, 20.0)], -33.0),
+            (vec![(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)], -23.0),
+            (vec![(-72.0, 10.0), (-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0), (-72.0, 10.0)], -23.0),
+            (vec![(-26.0, 20.0), (-20.0, 20.1), (-26.0, 20.0)], -23.0)
+        ];
+        for (levels, expected) in cases {
+            let res = meter.integrated(&sections(&levels));
+            assert!((res - expected).abs() < 0.1, "{:?}: {}", levels, res);
+        }
+        assert_eq!(meter.integrated(&[vec![0.0; 48000], vec![0.0; 48000]]), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_momentary_and_short_term() {
+        let meter = LoudnessMeter::new(RATE, 2);
+        let momentary = meter.momentary(&sections(&[(-20.0, 0.18), (-30.0, 0.22)].repeat(50)));
+        assert!(momentary.iter().all(|l| (l + 23.0).abs() < 0.1), "{:?}", momentary);
+
+        let short_term = meter.short_term(&sections(&[(-20.0, 1.34), (-30.0, 1.66)].repeat(7)));
+        assert_eq!(short_term.len(), 210 - 29);
+        assert!(short_term.iter().all(|l| (l + 23.0).abs() < 0.1), "{:?}", short_term);
+    }
+
+    #[test]
+    fn test_range() {
+        let meter = LoudnessMeter::new(RATE, 2);
+        let cases: Vec<(Vec<(f64, f64)>, f64)> = vec![
+            (vec![(-20.0, 20.0), (-30.0, 20.0)], 10.0),
+            (vec![(-20.0, 20.0), (-15.0, 20.0)], 5.0),
+            (vec![(-40.0, 20.0), (-20.0, 20.0)], 20.0),
+            (vec![(-50.0, 20.0), (-35.0, 20.0), (-20.0, 20.0), (-35.0, 20.0), (-50.0, 20.0)], 15.0)
+        ];
+        for (levels, expected) in cases {
+            let res = meter.range(&sections(&levels));
+            assert!((res - expected).abs() < 1.0, "{:?}: {}", levels, res);
+        }
+
+        // A NaN sample turns the K-weighted signal to NaN from there on, and the absolute
+        // gate drops those blocks.
+        let mut channels = sections(&[(-20.0, 20.0), (-30.0, 20.0), (-20.0, 10.0)]);
+        channels[0][40 * RATE as usize] = f32::NAN;
+        let res = meter.range(&channels);
+        assert!((res - 10.0).abs() < 1.0, "{}", res);
+    }
+
+    #[test]
+    #[should_panic(expected = "no 100 ms segments")]
+    fn test_rate_below_segment() {
+        LoudnessMeter::new(8, 1);
+    }
+
+    #[test]
+    fn test_channel_weights() {
+        // L, R and C at -28 dBFS and the surrounds at -30 dBFS, each surround weighted by 1.41.
+        let (front, surround) = (sections(&[(-28.0, 10.0)]).remove(0), sections(&[(-30.0, 10.0)]).remove(0));
+        let channels = vec![front.clone(), front.clone(), front, surround.clone(), surround];
+        let power = |db: f64| 10f64.powf(db / 10.0) / 2.0;
+        // K-weighting lifts 1 kHz by the 0.691 dB that the loudness formula takes off.
+        let expected = 10.0 * (3.0 * power(-28.0) + 2.0 * 1.41 * power(-30.0)).log10();
+
+        let res = LoudnessMeter::new(RATE, 5).integrated(&channels);
+        assert!((res - expected).abs() < 0.1, "{} != {}", res, expected);
+
+        let mut with_lfe = channels.clone();
+        with_lfe.insert(3, vec![0.5; channels[0].len()]);
+        assert!((LoudnessMeter::new(RATE, 6).integrated(&with_lfe) - res).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_true_peak() {
+        // A quarter sample rate sine at 45 degrees has its samples 3 dB below its peak.
+        let amplitude = 10f64.powf(-6.0 / 20.0);
+        let src: Vec<f32> = (0..RATE as usize / 2)
+            .map(|n| (amplitude * (::std::f64::consts::PI * (n as f64 / 2.0 + 0.25)).sin()) as f32).collect();
+        let sample_peak = 20.0 * src.iter().fold(0.0f32, |m, x| m.max(x.abs())).log10();
+        assert!((sample_peak + 9.01).abs() < 0.01);
+
+        let res = LoudnessMeter::new(RATE, 1).true_peak(&[src]);
+        assert!(res > -6.4 && res < -5.8, "{}", res);
+    }
+
+    #[test]
+    fn test_measure_wave() {
+        let channels = sections(&[(-23.0, 4.0)]);
+        let wave = Wave::from_channels(&channels, RATE);
+        let res = LoudnessMeter::new(RATE, 2).measure_wave(&wave);
+
+        assert!((res.integrated + 23.0).abs() < 0.1);
+        assert_eq!((res.momentary.len(), res.short_term.len()), (37, 11));
+        assert!(res.range < 0.1);
+        assert!((res.true_peak + 23.0).abs() < 0.1, "{}", res.true_peak);
+    }
+}
@@ -0,0 +1,302 @@
+use dct::dct2;
+use stft::Stft;
+use window::{ Symmetry, hann };
+use Wave;
+
+/// Mel scale variants. HTK is logarithmic throughout, Slaney (as in the Auditory
+/// Toolbox) is linear below 1 kHz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MelScale {
+    Htk,
+    Slaney
+}
+
+const SLANEY_LINEAR: f32 = 200.0 / 3.0;
+const SLANEY_BREAK: f32 = 1000.0;
+
+fn slaney_log_step() -> f32 { 6.4f32.ln() / 27.0 }
+
+impl MelScale {
+    pub fn hz_to_mel(&self, freq: f32) -> f32 {
+        match *self {
+            MelScale::Htk => 2595.0 * (1.0 + freq / 700.0).log10(),
+            MelScale::Slaney => match freq {
+                f if f < SLANEY_BREAK => f / SLANEY_LINEAR,
+                f => SLANEY_BREAK / SLANEY_LINEAR + (f / SLANEY_BREAK).ln() / slaney_log_step()
+            }
+        }
+    }
+
+    pub fn mel_to_hz(&self, mel: f32) -> f32 {
+        let knee = SLANEY_BREAK / SLANEY_LINEAR;
+        match *self {
+            MelScale::Htk => 700.0 * (10f32.powf(mel / 2595.0) - 1.0),
+            MelScale::Slaney => match mel {
+                m if m < knee => m * SLANEY_LINEAR,
+                m => SLANEY_BREAK * (slaney_log_step() * (m - knee)).exp()
+            }
+        }
+    }
+}
+
+/// Triangular filters evenly spaced on a mel scale over the power spectrum bins
+/// `0..=fft_len / 2`. HTK filters peak at 1; Slaney filters have unit area in Hz, so
+/// wide high bands are not louder than narrow low ones.
+#[derive(Clone, Debug)]
+pub struct MelFilterbank {
+    pub scale: MelScale,
+    pub sample_rate: u32,
+    pub fft_len: usize,
+    /// Band edges in Hz; band `m` spans `edges[m]..edges[m + 2]` and peaks at `edges[m + 1]`.
+    pub edges: Vec<f32>,
+    pub weights: Vec<Vec<f32>>
+}
+
+impl MelFilterbank {
+    pub fn new(scale: MelScale, bands: usize, fft_len: usize, sample_rate: u32) -> Self {
+        MelFilterbank::with_range(scale, bands, fft_len, sample_rate, 0.0, sample_rate as f32 / 2.0)
+    }
+
+    pub fn with_range(scale: MelScale, bands: usize, fft_len: usize, sample_rate: u32, min: f32, max: f32) -> Self {
+        let (lo, hi) = (scale.hz_to_mel(min), scale.hz_to_mel(max));
+        let edges: Vec<_> = (0..bands + 2)
+            .map(|i| scale.mel_to_hz(lo + (hi - lo) * i as f32 / (bands + 1) as f32)).collect();
+        let bin_width = sample_rate as f32 / fft_len as f32;
+
+        let weights = edges.windows(3).map(|e| (0..fft_len / 2 + 1).map(|k| {
+            let f = k as f32 * bin_width;
+            let w = ((f - e[0]) / (e[1] - e[0])).min((e[2] - f) / (e[2] - e[1])).max(0.0);
+            match scale {
+                MelScale::Htk => w,
+                MelScale::Slaney => w * 2.0 / (e[2] - e[0])
+            }
+        }).collect()).collect();
+
+        MelFilterbank { scale, sample_rate, fft_len, edges, weights }
+    }
+
+    pub fn bands(&self) -> usize { self.weights.len() }
+
+    pub fn center_frequencies(&self) -> Vec<f32> {
+        self.edges[1..self.edges.len() - 1].to_vec()
+    }
+
+    /// Mel band energies of one power spectrum frame.
+    pub fn apply(&self, power: &[f32]) -> Vec<f32> {
+        self.weights.iter().map(|w| w.iter().zip(power.iter()).map(|(a, b)| a * b).sum()).collect()
+    }
+}
+
+fn power_db(power: f32) -> f32 { 10.0 * power.max(1e-10).log10() }
+
+/// HTK style sinusoidal liftering `1 + L / 2 sin(pi n / L)`, a no-op for `lifter = 0`.
+pub fn lifter(coeffs: &mut [f32], lifter: usize) {
+    if lifter > 0 {
+        let l = lifter as f32;
+        for (n, c) in coeffs.iter_mut().enumerate() {
+            *c *= 1.0 + l / 2.0 * (::std::f32::consts::PI * n as f32 / l).sin();
+        }
+    }
+}
+
+/// Regression deltas over `width` frames on each side, repeating the edge frames.
+pub fn deltas(features: &[Vec<f32>], width: usize) -> Vec<Vec<f32>> {
+    assert!(width > 0, "deltas need at least one frame on each side");
+    let last = features.len().saturating_sub(1);
+    let norm = 2.0 * (1..width + 1).map(|n| (n * n) as f32).sum::<f32>();
+    (0..features.len()).map(|t| (0..features[t].len()).map(|d| (1..width + 1).map(|n| {
+        let (next, prev) = (&features[(t + n).min(last)], &features[t.saturating_sub(n)]);
+        n as f32 * (next[d] - prev[d])
+    }).sum::<f32>() / norm).collect()).collect()
+}
+
+/// Log-mel spectrogram and MFCC extractor. Each STFT frame's power spectrum goes
+/// through `bank`, is converted to dB, and for MFCCs the first `coefficients` values
+/// of its orthonormal DCT-II are liftered.
+#[derive(Clone, Debug)]
+pub struct Mfcc {
+    pub stft: Stft<f32>,
+    pub bank: MelFilterbank,
+    pub coefficients: usize,
+    pub lifter: usize
+}
+
+impl Mfcc {
+    /// 13 coefficients over `bands` Slaney bands with a periodic Hann window of
+    /// `frame_len` samples, zero padded to the next power of two, and a quarter frame hop.
+    pub fn new(sample_rate: u32, frame_len: usize, bands: usize) -> Self {
+        let stft = Stft::new(hann(frame_len, Symmetry::Periodic), frame_len / 4);
+        let bank = MelFilterbank::new(MelScale::Slaney, bands, stft.fft_len, sample_rate);
+        Mfcc { stft, bank, coefficients: 13, lifter: 22 }
+    }
+
+    pub fn log_mel(&self, src: &[f32]) -> Vec<Vec<f32>> {
+        assert_eq!(self.stft.fft_len, self.bank.fft_len, "stft and filterbank fft lengths differ");
+        self.stft.frames(src).map(|frame| {
+            let power: Vec<_> = frame.iter().take(self.stft.fft_len / 2 + 1).map(|c| c.norm_sqr()).collect();
+            self.bank.apply(&power).into_iter().map(power_db).collect()
+        }).collect()
+    }
+
+    pub fn from_log_mel(&self, log_mel: &[f32]) -> Vec<f32> {
+        let mut coeffs: Vec<_> = dct2(log_mel).into_iter().take(self.coefficients).collect();
+        lifter(&mut coeffs, self.lifter);
+        coeffs
+    }
+
+    pub fn compute(&self, src: &[f32]) -> Vec<Vec<f32>> {
+        self.log_mel(src).iter().map(|frame| self.from_log_mel(frame)).collect()
+    }
+
+    pub fn compute_wave(&self, wave: &Wave) -> Vec<Vec<f32>> {
+        assert_eq!(wave.format.sample_rate, self.bank.sample_rate, "wave and filterbank sample rates differ");
+        self.compute(&wave.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ MelFilterbank, MelScale, Mfcc, deltas, lifter };
+    use synth::{ mix, noise, sine };
+
+    const RATE: u32 = 16000;
+
+    #[test]
+    fn test_scales() {
+        let htk = MelScale::Htk;
+        assert!((htk.hz_to_mel(1000.0) - 999.9855).abs() < 1e-2);
+        assert!((htk.hz_to_mel(700.0) - 781.1729).abs() < 1e-2);
+        assert!((MelScale::Slaney.hz_to_mel(500.0) - 7.5).abs() < 1e-5);
+        assert!((MelScale::Slaney.hz_to_mel(1000.0) - 15.0).abs() < 1e-5);
+        assert!((MelScale::Slaney.hz_to_mel(6400.0) - 42.0).abs() < 1e-4);
+
+        for &scale in &[MelScale::Htk, MelScale::Slaney] {
+            for &f in &[0.0, 200.0, 999.0, 1000.0, 4000.0, 8000.0] {
+                assert!((scale.mel_to_hz(scale.hz_to_mel(f)) - f).abs() < 1e-2 * (1.0 + f / 1000.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_filterbank_shape() {
+        let fft_len = 8192;
+        let bin_width = RATE as f32 / fft_len as f32;
+
+        let htk = MelFilterbank::new(MelScale::Htk, 40, fft_len, RATE);
+        assert_eq!((htk.bands(), htk.weights[0].len()), (40, fft_len / 2 + 1));
+        assert_eq!(htk.edges[0], 0.0);
+        assert!((htk.edges[41] - 8000.0).abs() < 0.1);
+        for (w, &centre) in htk.weights.iter().zip(htk.center_frequencies().iter()) {
+            let peak = w.iter().cloned().fold(0.0, f32::max);
+            assert!(peak > 0.98 && peak <= 1.0);
+            let k = (centre / bin_width).round() as usize;
+            assert!(w[k] >= w[k - 1] && w[k] >= w[k + 1]);
+        }
+
+        let slaney = MelFilterbank::with_range(MelScale::Slaney, 40, fft_len, RATE, 20.0, 7600.0);
+        for w in &slaney.weights {
+            let area = w.iter().sum::<f32>() * bin_width;
+            assert!((area - 1.0).abs() < 0.02, "{}", area);
+        }
+    }
+
+    // Weights at 16 kHz with a 512 point FFT from a double precision reimplementation of
+    // librosa's `filters.mel` formulas (`htk` and Slaney `norm`), not from librosa itself.
+    #[test]
+    fn test_filterbank_reference() {
+        let slaney = MelFilterbank::new(MelScale::Slaney, 10, 512, RATE);
+        let htk = MelFilterbank::new(MelScale::Htk, 10, 512, RATE);
+        let expected = [
+            (&slaney, 0, 1, 0.000415589), (&slaney, 0, 3, 0.00124677), (&slaney, 0, 9, 0.00355322),
+            (&slaney, 4, 36, 0.000131439), (&slaney, 4, 37, 0.000337363), (&slaney, 4, 47, 0.00236733),
+            (&slaney, 9, 146, 7.1227e-06), (&slaney, 9, 148, 3.14774e-05), (&slaney, 9, 193, 0.0005782),
+            (&htk, 0, 1, 0.1734), (&htk, 0, 3, 0.5202), (&htk, 9, 155, 0.0293565), (&htk, 9, 156, 0.0514169)
+        ];
+        for &(bank, band, bin, value) in &expected {
+            let w = bank.weights[band][bin];
+            assert!((w - value).abs() < 1e-4 * value, "{:?} {} {}: {} != {}", bank.scale, band, bin, w, value);
+        }
+        assert_eq!(slaney.weights[0][0], 0.0);
+        assert_eq!(slaney.weights[9][145], 0.0);
+    }
+
+    #[test]
+    fn test_mfcc_regression() {
+        // The usual 25 ms frame at 16 kHz, which the STFT pads to 512 points. Frame 10
+        // starts at sample 700, clear of the padding, and the noise keeps every band well
+        // above single precision rounding. The expected values come from a double
+        // precision rendering of librosa's `feature.mfcc` steps (power to dB, orthonormal
+        // DCT-II, liftering of 22) fed this crate's padded frame and `synth::noise`, so
+        // librosa alone cannot regenerate them: they are regression values, not an
+        // independent reference.
+        let mfcc = Mfcc::new(RATE, 400, 40);
+        assert_eq!((mfcc.stft.fft_len, mfcc.bank.fft_len), (512, 512));
+        let src = mix(&[sine(1000.0, 0.5, RATE, 8000), sine(3000.0, 0.25, RATE, 8000), noise(0.01, 8000, 5)]);
+        let coeffs = &mfcc.compute(&src)[10];
+
+        let expected = [
+            -199.7141, 19.6661, -98.0043, -93.9191, -266.4486, 105.016, 542.8821,
+            31.3502, -70.4751, -135.5181, -447.0814, 141.749, 380.386
+        ];
+        // Liftering scales single precision rounding of the dB values up to 12 times.
+        for (n, (c, e)) in coeffs.iter().zip(expected.iter()).enumerate() {
+            assert!((c - e).abs() < 0.2 + 1e-3 * e.abs(), "{}: {} != {}", n, c, e);
+        }
+    }
+
+    #[test]
+    fn test_tone_lands_in_its_band() {
+        let mfcc = Mfcc::new(RATE, 512, 40);
+        let centres = mfcc.bank.center_frequencies();
+        let log_mel = mfcc.log_mel(&sine(centres[20], 0.5, RATE, RATE as usize / 4));
+
+        for frame in &log_mel[4..log_mel.len() - 4] {
+            let loudest = (0..frame.len()).fold(0, |best, m| if frame[m] > frame[best] { m } else { best });
+            assert_eq!(loudest, 20);
+        }
+    }
+
+    #[test]
+    fn test_mfcc_definition() {
+        let mut mfcc = Mfcc::new(RATE, 512, 8);
+        let log_mel = [-20.0, -10.0, -5.0, -8.0, -12.0, -30.0, -40.0, -60.0];
+        mfcc.lifter = 0;
+
+        let coeffs = mfcc.from_log_mel(&log_mel);
+        assert_eq!(coeffs.len(), 8);
+        for (n, c) in coeffs.iter().enumerate() {
+            let scale = if n == 0 { (1.0f32 / 8.0).sqrt() } else { (2.0f32 / 8.0).sqrt() };
+            let expected = scale * log_mel.iter().enumerate()
+                .map(|(m, x)| x * (::std::f32::consts::PI * n as f32 * (m as f32 + 0.5) / 8.0).cos()).sum::<f32>();
+            assert!((c - expected).abs() < 1e-3, "{}: {} != {}", n, c, expected);
+        }
+
+        let constant = mfcc.from_log_mel(&[3.0; 8]);
+        assert!((constant[0] - 3.0 * 8f32.sqrt()).abs() < 1e-4);
+        assert!(constant[1..].iter().all(|c| c.abs() < 1e-4));
+
+        assert_eq!(Mfcc::new(RATE, 512, 40).compute(&sine(440.0, 0.5, RATE, 4000))[0].len(), 13);
+    }
+
+    #[test]
+    fn test_lifter_and_deltas() {
+        let mut coeffs = [1.0; 4];
+        lifter(&mut coeffs, 22);
+        assert_eq!(coeffs[0], 1.0);
+        assert!((coeffs[1] - (1.0 + 11.0 * (::std::f32::consts::PI / 22.0).sin())).abs() < 1e-5);
+
+        let ramp: Vec<Vec<f32>> = (0..10).map(|t| vec![2.0 * t as f32, 5.0]).collect();
+        let d = deltas(&ramp, 2);
+        assert_eq!(d.len(), 10);
+        for frame in &d[2..8] {
+            assert!((frame[0] - 2.0).abs() < 1e-6 && frame[1] == 0.0);
+        }
+        assert!((d[0][0] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one frame")]
+    fn test_deltas_without_width() {
+        deltas(&[vec![1.0], vec![2.0]], 0);
+    }
+}
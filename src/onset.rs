@@ -0,0 +1,307 @@
+use complex::Complex;
+use pitch::parabolic_offset;
+use stft::Stft;
+use window::{ Symmetry, hann };
+use xcorr::autocorrelation;
+use Wave;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnsetFunction {
+    /// Sum of the magnitude increases over all bins.
+    SpectralFlux,
+    /// Increase of the energy weighted by bin number, which favours percussive onsets.
+    HighFrequencyContent,
+    /// Distance of each bin from its value predicted by constant magnitude and phase
+    /// advance, counted only for bins that grow, so soft tonal onsets are caught too.
+    ComplexDomain
+}
+
+/// Onset detection function of a sequence of STFT frames, one value per frame.
+pub fn onset_strength(frames: &[Vec<Complex<f32>>], function: OnsetFunction) -> Vec<f32> {
+    let bins = frames.first().map_or(0, |f| f.len() / 2 + 1);
+    let zero = vec![Complex::default(); bins];
+    let at = |t: usize, back: usize| match t.checked_sub(back) {
+        Some(i) => &frames[i][..bins],
+        None => &zero[..]
+    };
+
+    (0..frames.len()).map(|t| {
+        let (cur, prev, prev2) = (at(t, 0), at(t, 1), at(t, 2));
+        match function {
+            OnsetFunction::SpectralFlux => cur.iter().zip(prev)
+                .map(|(x, y)| (x.norm() - y.norm()).max(0.0)).sum(),
+            OnsetFunction::HighFrequencyContent => {
+                let hfc = |f: &[Complex<f32>]| f.iter().enumerate().map(|(k, x)| k as f32 * x.norm_sqr()).sum::<f32>();
+                (hfc(cur) - hfc(prev)).max(0.0) / bins as f32
+            },
+            OnsetFunction::ComplexDomain => cur.iter().zip(prev.iter().zip(prev2)).map(|(x, (y, z))| {
+                let predicted = Complex::from_polar(y.norm(), 2.0 * y.arg() - z.arg());
+                match x.norm() >= y.norm() {
+                    true => (*x - predicted).norm(),
+                    false => 0.0
+                }
+            }).sum()
+        }
+    }).collect()
+}
+
+/// Picks peaks of an onset detection function. A frame is an onset when it is the
+/// maximum over `pre_max` frames before and `post_max` after, exceeds the mean over
+/// `pre_avg` before and `post_avg` after by `delta`, and is at least `wait` frames past
+/// the previous onset. `delta` is relative to the strongest value.
+#[derive(Clone, Debug)]
+pub struct PeakPicker {
+    pub pre_max: usize,
+    pub post_max: usize,
+    pub pre_avg: usize,
+    pub post_avg: usize,
+    pub delta: f32,
+    pub wait: usize
+}
+
+impl Default for PeakPicker {
+    fn default() -> Self {
+        PeakPicker { pre_max: 3, post_max: 3, pre_avg: 10, post_avg: 10, delta: 0.2, wait: 10 }
+    }
+}
+
+impl PeakPicker {
+    pub fn pick(&self, novelty: &[f32]) -> Vec<usize> {
+        let peak = novelty.iter().cloned().fold(0.0, f32::max);
+        if peak <= 0.0 {
+            return Vec::new();
+        }
+        let values: Vec<_> = novelty.iter().map(|x| x / peak).collect();
+        let span = |t: usize, before: usize, after: usize| &values[t.saturating_sub(before)..(t + after + 1).min(values.len())];
+
+        let mut onsets: Vec<usize> = Vec::new();
+        for (t, &x) in values.iter().enumerate() {
+            let local = span(t, self.pre_max, self.post_max).iter().cloned().fold(0.0, f32::max);
+            let around = span(t, self.pre_avg, self.post_avg);
+            let mean = around.iter().sum::<f32>() / around.len() as f32;
+            let waited = onsets.last().is_none_or(|&last| t - last >= self.wait);
+            if x > 0.0 && x >= local && x >= mean + self.delta && waited {
+                onsets.push(t);
+            }
+        }
+        onsets
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OnsetDetector {
+    pub function: OnsetFunction,
+    pub frame_len: usize,
+    pub hop: usize,
+    pub picker: PeakPicker
+}
+
+impl Default for OnsetDetector {
+    fn default() -> Self {
+        OnsetDetector { function: OnsetFunction::SpectralFlux, frame_len: 1024, hop: 256, picker: PeakPicker::default() }
+    }
+}
+
+impl OnsetDetector {
+    fn stft(&self) -> Stft<f32> {
+        Stft::new(hann(self.frame_len, Symmetry::Periodic), self.hop)
+    }
+
+    pub fn strength(&self, src: &[f32]) -> Vec<f32> {
+        onset_strength(&self.stft().analyze(src), self.function)
+    }
+
+    /// Onset detection function values per second.
+    pub fn frame_rate(&self, sample_rate: u32) -> f32 {
+        sample_rate as f32 / self.hop as f32
+    }
+
+    pub fn frame_time(&self, index: usize, sample_rate: u32) -> f32 {
+        self.stft().frame_time(index, sample_rate)
+    }
+
+    /// Onset times in seconds.
+    pub fn detect(&self, src: &[f32], sample_rate: u32) -> Vec<f32> {
+        self.picker.pick(&self.strength(src)).into_iter().map(|t| self.frame_time(t, sample_rate)).collect()
+    }
+
+    pub fn detect_wave(&self, wave: &Wave) -> Vec<f32> {
+        self.detect(&wave.data, wave.format.sample_rate)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Beats {
+    pub tempo: f32,
+    /// Frame indices of the beats in the onset detection function.
+    pub frames: Vec<usize>
+}
+
+/// Tempo from the autocorrelation of an onset detection function, weighted by a
+/// log-Gaussian prior around `prior_bpm` that is `prior_width` octaves wide, and
+/// beats by dynamic programming that trades onset strength against deviation from
+/// that tempo, scaled by `tightness`.
+#[derive(Clone, Debug)]
+pub struct BeatTracker {
+    pub min_bpm: f32,
+    pub max_bpm: f32,
+    pub prior_bpm: f32,
+    pub prior_width: f32,
+    pub tightness: f32
+}
+
+impl Default for BeatTracker {
+    fn default() -> Self {
+        BeatTracker { min_bpm: 40.0, max_bpm: 240.0, prior_bpm: 120.0, prior_width: 1.0, tightness: 100.0 }
+    }
+}
+
+fn normalized(novelty: &[f32]) -> Vec<f32> {
+    let mean = novelty.iter().sum::<f32>() / novelty.len().max(1) as f32;
+    let deviation = (novelty.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / novelty.len().max(1) as f32).sqrt();
+    // A constant input would otherwise divide the rounding error of its mean.
+    let constant = novelty.iter().all(|&x| x == novelty[0]);
+    novelty.iter().map(|x| match deviation {
+        d if d > 0.0 && !constant => (x - mean) / d,
+        _ => 0.0
+    }).collect()
+}
+
+impl BeatTracker {
+    /// Tempo in BPM, `None` when `novelty` is too short to hold the lags of the BPM range
+    /// or has no periodicity within it, as for silence.
+    pub fn tempo(&self, novelty: &[f32], frame_rate: f32) -> Option<f32> {
+        let r = autocorrelation(&normalized(novelty));
+        let bpm = |lag: f32| 60.0 * frame_rate / lag;
+        let (min_lag, max_lag) = (
+            ((60.0 * frame_rate / self.max_bpm).floor() as usize).max(1),
+            ((60.0 * frame_rate / self.min_bpm).ceil() as usize).min(r.len().saturating_sub(2))
+        );
+        if max_lag <= min_lag {
+            return None;
+        }
+        let weighted = |lag: usize| r[lag] * (-0.5 * ((bpm(lag as f32) / self.prior_bpm).log2() / self.prior_width).powi(2)).exp();
+
+        let lag = (min_lag..max_lag + 1).fold(min_lag, |best, lag| if weighted(lag) > weighted(best) { lag } else { best });
+        if r[lag] <= 0.0 {
+            return None;
+        }
+        Some(bpm(lag as f32 + parabolic_offset(r[lag - 1], r[lag], r[lag + 1])))
+    }
+
+    /// Beats, or `None` without a tempo.
+    pub fn track(&self, novelty: &[f32], frame_rate: f32) -> Option<Beats> {
+        let tempo = self.tempo(novelty, frame_rate)?;
+        let period = 60.0 * frame_rate / tempo;
+        let strength = normalized(novelty);
+
+        let (mut score, mut back) = (strength.clone(), vec![None; strength.len()]);
+        for t in 0..strength.len() {
+            let (from, to) = ((t as f32 - 2.0 * period).max(0.0) as usize, (t as f32 - period / 2.0).max(0.0) as usize);
+            let best = (from..to).map(|prev| {
+                let penalty = self.tightness * ((t - prev) as f32 / period).ln().powi(2);
+                (prev, score[prev] - penalty)
+            }).fold(None, |best: Option<(usize, f32)>, cur| match best {
+                Some(b) if b.1 >= cur.1 => Some(b),
+                _ => Some(cur)
+            });
+            if let Some((prev, s)) = best {
+                score[t] += s;
+                back[t] = Some(prev);
+            }
+        }
+
+        let tail = strength.len().saturating_sub(period.round() as usize);
+        let mut frames = match (tail..strength.len()).fold(None, |best: Option<usize>, t| match best {
+            Some(b) if score[b] >= score[t] => Some(b),
+            _ => Some(t)
+        }) {
+            Some(last) => vec![last],
+            None => Vec::new()
+        };
+        while let Some(prev) = frames.last().and_then(|&t| back[t]) {
+            frames.push(prev);
+        }
+        frames.reverse();
+        Some(Beats { tempo, frames })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ BeatTracker, OnsetDetector, OnsetFunction, PeakPicker };
+    use std::f32::consts::PI;
+
+    const RATE: u32 = 44100;
+    const OCTAVE: [f32; 8] = [261.63, 293.66, 329.63, 349.23, 392.00, 440.00, 493.88, 523.25];
+
+    /// Consecutive faded notes as in `octave.rs`.
+    fn notes(count: usize, note_len: usize) -> Vec<f32> {
+        let fade = RATE as usize / 50;
+        (0..count * note_len).map(|i| {
+            let (idx, freq) = (i % note_len, OCTAVE[(i / note_len) % OCTAVE.len()]);
+            let x = 0.5 * (2.0 * PI * freq * idx as f32 / RATE as f32).sin();
+            x * (idx.min(note_len - 1 - idx).min(fade) as f32 / fade as f32)
+        }).collect()
+    }
+
+    #[test]
+    fn test_octave_onsets() {
+        let note_len = RATE as usize / 4;
+        let src = notes(8, note_len);
+
+        for &function in &[OnsetFunction::SpectralFlux, OnsetFunction::HighFrequencyContent, OnsetFunction::ComplexDomain] {
+            let detector = OnsetDetector { function, ..OnsetDetector::default() };
+            let onsets = detector.detect(&src, RATE);
+            assert_eq!(onsets.len(), 8, "{:?}: {:?}", function, onsets);
+            for (i, t) in onsets.iter().enumerate() {
+                assert!((t - 0.25 * i as f32).abs() < 0.03, "{:?}: onset {} at {}", function, i, t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_peak_picker() {
+        let mut novelty = vec![0.0; 100];
+        for &(t, x) in &[(10, 1.0), (12, 0.9), (40, 0.5), (70, 0.02)] {
+            novelty[t] = x;
+        }
+        assert_eq!(PeakPicker::default().pick(&novelty), vec![10, 40]);
+        assert!(PeakPicker::default().pick(&[0.0; 20]).is_empty());
+    }
+
+    #[test]
+    fn test_tempo_and_beats() {
+        let (note_len, count) = (RATE as usize / 2, 16);
+        let src = notes(count, note_len);
+        let detector = OnsetDetector::default();
+        let frame_rate = detector.frame_rate(RATE);
+
+        let beats = BeatTracker::default().track(&detector.strength(&src), frame_rate).expect("tempo");
+        assert!((beats.tempo - 120.0).abs() < 1.0, "{}", beats.tempo);
+        assert!(beats.frames.len() >= count - 1 && beats.frames.len() <= count + 1, "{:?}", beats.frames);
+
+        for &t in &beats.frames {
+            let time = detector.frame_time(t, RATE);
+            let offset = (time / 0.5 - (time / 0.5).round()).abs() * 0.5;
+            assert!(offset < 0.03, "beat at {}", time);
+        }
+    }
+
+    #[test]
+    fn test_short_novelty() {
+        // At 100 frames per second even 240 BPM needs lags of 25 frames.
+        let tracker = BeatTracker::default();
+        assert_eq!(tracker.tempo(&[], 100.0), None);
+        assert_eq!(tracker.tempo(&[1.0, 0.0, 1.0], 100.0), None);
+        assert_eq!(tracker.track(&[0.5; 20], 100.0), None);
+    }
+
+    #[test]
+    fn test_silence() {
+        let tracker = BeatTracker::default();
+        assert_eq!(tracker.tempo(&[0.0; 400], 100.0), None);
+        assert_eq!(tracker.tempo(&[0.3; 400], 100.0), None);
+        assert_eq!(tracker.track(&[0.0; 400], 100.0), None);
+    }
+}
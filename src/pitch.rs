@@ -0,0 +1,256 @@
+use float::Float;
+use xcorr::cross_correlation;
+use Wave;
+
+/// `c(lag) = sum_{j < head} src[j] src[j + lag]` for `lag` in `0..lags`.
+fn correlate(src: &[f32], head: usize, lags: usize) -> Vec<f32> {
+    cross_correlation(&src[..head], src).into_iter().skip(head - 1).take(lags).collect()
+}
+
+/// Energy of `src[lag..lag + head]` for `lag` in `0..=max_lag`.
+fn energies(src: &[f32], head: usize, max_lag: usize) -> Vec<f32> {
+    let squares: Vec<f32> = ::std::iter::once(0.0)
+        .chain(src.iter().scan(0.0, |acc, x| { *acc += x * x; Some(*acc) })).collect();
+    (0..max_lag + 1).map(|lag| squares[lag + head] - squares[lag]).collect()
+}
+
+/// Vertex offset in `[-0.5, 0.5]` of the parabola through three neighbouring values.
+pub fn parabolic_offset<T: Float>(a: T, b: T, c: T) -> T {
+    let (half, d) = (T::from_f64(0.5), a - T::from_f64(2.0) * b + c);
+    match d.abs() > T::from_f64(1e-12) {
+        true => (half * (a - c) / d).max(-half).min(half),
+        false => T::zero()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PitchMethod {
+    Yin,
+    Autocorrelation
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pitch {
+    /// Centre of the analysis frame in seconds.
+    pub time: f32,
+    /// Fundamental frequency in Hz, `None` for unvoiced frames.
+    pub frequency: Option<f32>,
+    /// Periodicity in `[0, 1]`: `1 - d'` for YIN, the normalized correlation otherwise.
+    pub confidence: f32
+}
+
+/// Frame-wise f0 tracker. A frame is voiced when its confidence reaches
+/// `1 - threshold`; YIN then takes the first dip of the normalized difference below
+/// `threshold`, and autocorrelation the first peak within 10% of the best.
+#[derive(Clone, Debug)]
+pub struct PitchDetector {
+    pub method: PitchMethod,
+    pub frame_len: usize,
+    pub hop: usize,
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    pub threshold: f32
+}
+
+impl Default for PitchDetector {
+    fn default() -> Self {
+        PitchDetector {
+            method: PitchMethod::Yin,
+            frame_len: 2048,
+            hop: 512,
+            min_frequency: 60.0,
+            max_frequency: 2000.0,
+            threshold: 0.15
+        }
+    }
+}
+
+impl PitchDetector {
+    /// Lags of `max_frequency` and `min_frequency`, the latter capped at half of `len`.
+    fn lag_range(&self, len: usize, sample_rate: u32) -> (usize, usize) {
+        let rate = sample_rate as f32;
+        let max = ((rate / self.min_frequency).ceil() as usize).min(len / 2);
+        (((rate / self.max_frequency).floor() as usize).max(2), max)
+    }
+
+    /// Cumulative mean normalized difference `d'(lag)` for `lag` in `0..=max_lag`.
+    fn yin_difference(&self, frame: &[f32], max_lag: usize) -> Vec<f32> {
+        let head = frame.len() - max_lag;
+        let energy = energies(frame, head, max_lag);
+
+        let cross = correlate(frame, head, max_lag + 1);
+        let mut running = 0.0;
+        (0..max_lag + 1).map(|lag| match lag {
+            0 => 1.0,
+            lag => {
+                let d = (energy[0] + energy[lag] - 2.0 * cross[lag]).max(0.0);
+                running += d;
+                match running {
+                    r if r > 0.0 => d * lag as f32 / r,
+                    _ => 1.0
+                }
+            }
+        }).collect()
+    }
+
+    fn yin(&self, frame: &[f32], sample_rate: u32) -> (f32, f32) {
+        let (min_lag, max_lag) = self.lag_range(frame.len(), sample_rate);
+        let d = self.yin_difference(frame, max_lag);
+
+        let lag = match (min_lag..max_lag).find(|&lag| d[lag] < self.threshold) {
+            Some(first) => (first..max_lag).find(|&lag| d[lag + 1] >= d[lag]).unwrap_or(max_lag - 1),
+            None => (min_lag..max_lag).fold(min_lag, |best, lag| if d[lag] < d[best] { lag } else { best })
+        };
+        let offset = parabolic_offset(d[lag - 1], d[lag], d[lag + 1]);
+        (lag as f32 + offset, (1.0 - d[lag]).max(0.0))
+    }
+
+    fn autocorrelation(&self, frame: &[f32], sample_rate: u32) -> (f32, f32) {
+        let (min_lag, max_lag) = self.lag_range(frame.len(), sample_rate);
+        let head = frame.len() - max_lag;
+        let energy = energies(frame, head, max_lag);
+
+        let cross = correlate(frame, head, max_lag + 1);
+        let r: Vec<f32> = (0..max_lag + 1).map(|lag| match (energy[0] * energy[lag]).sqrt() {
+            norm if norm > 1e-12 => cross[lag] / norm,
+            _ => 0.0
+        }).collect();
+
+        let is_peak = |lag: usize| r[lag] >= r[lag - 1] && r[lag] >= r[lag + 1];
+        let best = (min_lag..max_lag).filter(|&lag| is_peak(lag)).map(|lag| r[lag]).fold(0.0, f32::max);
+        let lag = (min_lag..max_lag).find(|&lag| is_peak(lag) && r[lag] >= 0.9 * best).unwrap_or(min_lag);
+        let offset = parabolic_offset(r[lag - 1], r[lag], r[lag + 1]);
+        (lag as f32 + offset, r[lag].clamp(0.0, 1.0))
+    }
+
+    /// Estimates the pitch of one frame, usually of `frame_len` samples, returning the
+    /// frequency in Hz and the confidence. Lags beyond half the frame are not searched,
+    /// and frames too short to hold two periods of `max_frequency` never have a pitch.
+    pub fn detect_frame(&self, frame: &[f32], sample_rate: u32) -> (Option<f32>, f32) {
+        let energy: f32 = frame.iter().map(|x| x * x).sum();
+        let (min_lag, max_lag) = self.lag_range(frame.len(), sample_rate);
+        if energy <= 1e-10 * frame.len() as f32 || min_lag + 1 >= max_lag {
+            return (None, 0.0);
+        }
+
+        let (lag, confidence) = match self.method {
+            PitchMethod::Yin => self.yin(frame, sample_rate),
+            PitchMethod::Autocorrelation => self.autocorrelation(frame, sample_rate)
+        };
+        match confidence >= 1.0 - self.threshold {
+            true => (Some(sample_rate as f32 / lag), confidence),
+            false => (None, confidence)
+        }
+    }
+
+    pub fn track(&self, src: &[f32], sample_rate: u32) -> Vec<Pitch> {
+        assert!(self.hop > 0, "hop must be positive");
+        let frames = match src.len() {
+            len if len < self.frame_len => 0,
+            len => (len - self.frame_len) / self.hop + 1
+        };
+        (0..frames).map(|i| {
+            let start = i * self.hop;
+            let (frequency, confidence) = self.detect_frame(&src[start..start + self.frame_len], sample_rate);
+            let time = (start + self.frame_len / 2) as f32 / sample_rate as f32;
+            Pitch { time, frequency, confidence }
+        }).collect()
+    }
+
+    pub fn track_wave(&self, wave: &Wave) -> Vec<Pitch> {
+        self.track(&wave.data, wave.format.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ PitchDetector, PitchMethod };
+    use synth::{ harmonics, noise, silence };
+
+    const RATE: u32 = 44100;
+    const ORGAN: [f32; 5] = [0.5, 1.0, 0.7, 0.5, 0.3];
+
+    fn detectors() -> Vec<PitchDetector> {
+        vec![
+            PitchDetector::default(),
+            PitchDetector { method: PitchMethod::Autocorrelation, ..PitchDetector::default() }
+        ]
+    }
+
+    fn piano(freq: f32, len: usize) -> Vec<f32> {
+        let decays = [4.0, 2.0, 1.0, 0.5, 0.2];
+        (0..5).map(|k| {
+            let mut amps = [0.0; 5];
+            amps[k] = [1.0, 0.8, 0.6, 0.5, 0.4][k];
+            harmonics(freq, &amps, RATE, len).into_iter().enumerate()
+                .map(|(i, x)| x * (-5.0 * i as f32 / (RATE as f32 * decays[k])).exp()).collect::<Vec<_>>()
+        }).fold(vec![0.0; len], |acc, p| acc.iter().zip(p).map(|(a, b)| a + b).collect())
+    }
+
+    #[test]
+    fn test_synthesized_tones() {
+        for detector in detectors() {
+            for &freq in &[82.41, 110.0, 261.63, 440.0, 987.77] {
+                for src in &[harmonics(freq, &ORGAN, RATE, RATE as usize / 4), piano(freq, RATE as usize / 4)] {
+                    let track = detector.track(src, RATE);
+                    assert_eq!(track.len(), (RATE as usize / 4 - 2048) / 512 + 1);
+
+                    for pitch in &track {
+                        let f = pitch.frequency.expect("voiced");
+                        assert!((f / freq - 1.0).abs() < 2e-3, "{:?}: {} != {}", detector.method, f, freq);
+                        assert!(pitch.confidence > 0.9);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unvoiced() {
+        let mut src = silence(4096);
+        src.extend(noise(0.5, 8192, 3));
+        for detector in detectors() {
+            let track = detector.track(&src, RATE);
+            assert!(track.iter().take(4).all(|p| p.frequency.is_none() && p.confidence == 0.0));
+            let voiced = track.iter().filter(|p| p.frequency.is_some()).count();
+            assert!(voiced <= 1, "{:?}: {} noise frames voiced", detector.method, voiced);
+        }
+    }
+
+    #[test]
+    fn test_frame_too_short_for_range() {
+        // 100 Hz needs a lag of 441 samples, beyond half of a 256 sample frame.
+        let src = harmonics(440.0, &ORGAN, RATE, 256);
+        for detector in detectors() {
+            let detector = PitchDetector { frame_len: 256, max_frequency: 100.0, ..detector };
+            assert_eq!(detector.detect_frame(&src, RATE), (None, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_frame_shorter_than_frame_len() {
+        // The default detector searches lags up to 735 samples in its 2048 sample frames;
+        // a shorter frame is searched up to half its length instead.
+        let src = harmonics(440.0, &ORGAN, RATE, 400);
+        for detector in detectors() {
+            assert_eq!(detector.detect_frame(&src[..100], RATE).0, None);
+            let (frequency, _) = detector.detect_frame(&src, RATE);
+            let frequency = frequency.expect("pitch");
+            assert!((frequency - 440.0).abs() < 5.0, "{:?}: {}", detector.method, frequency);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "hop must be positive")]
+    fn test_without_hop() {
+        PitchDetector { hop: 0, ..PitchDetector::default() }.track(&[0.0; 4096], RATE);
+    }
+
+    #[test]
+    fn test_time_axis() {
+        let src = harmonics(220.0, &ORGAN, RATE, RATE as usize);
+        let track = PitchDetector::default().track(&src, RATE);
+        assert!((track[0].time - 1024.0 / RATE as f32).abs() < 1e-6);
+        assert!((track[10].time - track[9].time - 512.0 / RATE as f32).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,202 @@
+use complex::Complex;
+use czt::fft_any;
+use float::Float;
+use Wave;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Averaging {
+    Mean,
+    /// Bias-corrected median, robust against transients in a few segments. A NaN in
+    /// any segment still makes its bins NaN, as with the mean.
+    Median
+}
+
+/// Welch's method: the signal is cut into windowed segments of `window.len()` samples
+/// overlapping by `overlap`, and their periodograms are averaged.
+#[derive(Clone, Debug)]
+pub struct Welch<T> {
+    pub window: Vec<T>,
+    pub overlap: usize,
+    pub averaging: Averaging
+}
+
+/// One-sided power spectral density in V^2/Hz.
+#[derive(Clone, Debug)]
+pub struct Psd<T> {
+    pub frequencies: Vec<T>,
+    pub density: Vec<T>,
+    /// Equivalent noise bandwidth of one bin in Hz.
+    pub enbw: T
+}
+
+fn median_bias<T: Float>(n: usize) -> T {
+    (1..(n - 1) / 2 + 1).map(|i| T::from_usize(2 * i))
+        .fold(T::one(), |acc, k| acc + T::one() / (k + T::one()) - T::one() / k)
+}
+
+fn median<T: Float>(mut values: Vec<T>) -> T {
+    if let Some(&nan) = values.iter().find(|x| x.is_nan()) {
+        return nan;
+    }
+    values.sort_by(T::total_cmp);
+    let mid = values.len() / 2;
+    match values.len() % 2 {
+        0 => (values[mid - 1] + values[mid]) / T::from_f64(2.0),
+        _ => values[mid]
+    }
+}
+
+impl<T: Float> Welch<T> {
+    pub fn new(window: Vec<T>, overlap: usize) -> Self {
+        assert!(overlap < window.len(), "overlap {} must be shorter than the segment", overlap);
+        Welch { window, overlap, averaging: Averaging::Mean }
+    }
+
+    pub fn with_averaging(mut self, averaging: Averaging) -> Self {
+        self.averaging = averaging;
+        self
+    }
+
+    /// Number of full segments in `len` samples. A signal shorter than one segment is
+    /// zero padded to a single segment.
+    pub fn segment_count(&self, len: usize) -> usize {
+        let (seg, step) = (self.window.len(), self.window.len() - self.overlap);
+        match len {
+            len if len < seg => 1,
+            len => (len - seg) / step + 1
+        }
+    }
+
+    fn periodogram(&self, segment: &[T], scale: T) -> Vec<T> {
+        let len = self.window.len();
+        let frame = (0..len).map(|n| Complex::new(segment.get(n).cloned().unwrap_or_default() * self.window[n], T::zero()))
+            .collect();
+        fft_any(frame).into_iter().take(len / 2 + 1).enumerate().map(|(k, c)| match k {
+            0 => c.norm_sqr() * scale,
+            k if 2 * k == len => c.norm_sqr() * scale,
+            _ => c.norm_sqr() * scale * T::from_f64(2.0)
+        }).collect()
+    }
+
+    pub fn estimate(&self, src: &[T], sample_rate: u32) -> Psd<T> {
+        let (len, step, rate) = (self.window.len(), self.window.len() - self.overlap, T::from_f64(sample_rate as f64));
+        let (sum, power): (T, T) = (self.window.iter().cloned().sum(), self.window.iter().map(|&w| w * w).sum());
+        let scale = T::one() / (rate * power);
+
+        let count = self.segment_count(src.len());
+        let periodograms: Vec<_> = (0..count)
+            .map(|s| self.periodogram(&src[(s * step).min(src.len())..], scale)).collect();
+
+        let bins = len / 2 + 1;
+        let density = (0..bins).map(|k| match self.averaging {
+            Averaging::Mean => periodograms.iter().map(|p| p[k]).sum::<T>() / T::from_usize(count),
+            Averaging::Median => median(periodograms.iter().map(|p| p[k]).collect()) / median_bias(count)
+        }).collect();
+
+        Psd {
+            frequencies: (0..bins).map(|k| T::from_usize(k) * rate / T::from_usize(len)).collect(),
+            density,
+            enbw: rate * power / (sum * sum)
+        }
+    }
+}
+
+impl Welch<f32> {
+    pub fn estimate_wave(&self, wave: &Wave) -> Psd<f32> {
+        self.estimate(&wave.data, wave.format.sample_rate)
+    }
+}
+
+impl<T: Float> Psd<T> {
+    /// Density relative to a full-scale sine, in dBFS/Hz.
+    pub fn density_dbfs(&self) -> Vec<T> {
+        self.density.iter().map(|&d| to_dbfs(d)).collect()
+    }
+
+    /// Power per bin in V^2, calibrated so that a sinusoid centred on a bin reads its
+    /// mean power.
+    pub fn power(&self) -> Vec<T> {
+        self.density.iter().map(|&d| d * self.enbw).collect()
+    }
+
+    /// `power` in dBFS, so a full-scale sine reads 0 dB.
+    pub fn power_dbfs(&self) -> Vec<T> {
+        self.power().into_iter().map(to_dbfs).collect()
+    }
+
+    /// Mean power of the signal, the density integrated over frequency.
+    pub fn total_power(&self) -> T {
+        let width = self.frequencies.get(1).cloned().unwrap_or_default();
+        self.density.iter().cloned().sum::<T>() * width
+    }
+}
+
+fn to_dbfs<T: Float>(power: T) -> T {
+    T::from_f64(10.0) * (power / T::from_f64(0.5)).max(T::from_f64(1e-30)).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Averaging, Welch, median };
+    use synth::{ mix, noise, sine };
+    use window::{ Symmetry, hann };
+
+    const RATE: u32 = 48000;
+
+    #[test]
+    fn test_white_noise() {
+        // Uniform noise in [-1, 1) has variance 1/3, so a flat density of 2/3/fs.
+        let src = noise(1.0, RATE as usize * 4, 11);
+        let expected = 2.0 / 3.0 / RATE as f64;
+
+        for &averaging in &[Averaging::Mean, Averaging::Median] {
+            let psd = Welch::new(hann(1024, Symmetry::Periodic), 512).with_averaging(averaging).estimate(&src, RATE);
+            assert_eq!(psd.density.len(), 513);
+
+            let mean = psd.density[1..512].iter().sum::<f64>() / 511.0;
+            assert!((mean / expected - 1.0).abs() < 0.03, "{:?}: {} != {}", averaging, mean, expected);
+            assert!((psd.total_power() - 1.0 / 3.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_sine_power() {
+        let (len, bin) = (1024, 64);
+        let freq = bin as f64 * RATE as f64 / len as f64;
+        let src = mix(&[sine(freq, 0.5, RATE, RATE as usize), sine(10148.4375, 1.0, RATE, RATE as usize)]);
+        let window = hann(len, Symmetry::Periodic);
+
+        for &overlap in &[0, 512, 768] {
+            let psd = Welch::new(window.clone(), overlap).estimate(&src, RATE);
+            assert_eq!(psd.frequencies[bin], freq);
+            assert!((psd.enbw - 1.5 * RATE as f64 / len as f64).abs() < 1e-6);
+            assert!((psd.power()[bin] - 0.125).abs() < 1e-6);
+            assert!((psd.power_dbfs()[bin] + 6.0206).abs() < 1e-3);
+
+            let peak = psd.power_dbfs()[210..222].iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            assert!(peak < 0.0 && peak > -1.5, "{}", peak);
+            assert!((psd.total_power() - 0.625).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_segments() {
+        let welch = Welch::new(hann(256, Symmetry::Periodic), 128);
+        assert_eq!(welch.segment_count(100), 1);
+        assert_eq!(welch.segment_count(256), 1);
+        assert_eq!(welch.segment_count(1000), 6);
+        assert_eq!(welch.estimate(&[1.0; 100], 8000).density.len(), 129);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), 2.0);
+        assert!(median(vec![1.0, f64::NAN, 3.0, 2.0]).is_nan());
+        assert!(median(vec![1.0, -f64::NAN, 3.0, 2.0]).is_nan());
+
+        let mut src = noise(1.0, 1024, 9);
+        src[500] = f64::NAN;
+        let psd = Welch::new(hann(128, Symmetry::Periodic), 64).with_averaging(Averaging::Median).estimate(&src, 8000);
+        assert!(psd.density.iter().all(|x| x.is_nan()));
+    }
+}
@@ -0,0 +1,317 @@
+use complex::Complex;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Isa {
+    Avx,
+    Sse3,
+    Scalar
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn isa() -> Isa {
+    match (is_x86_feature_detected!("avx"), is_x86_feature_detected!("sse3")) {
+        (true, _) => Isa::Avx,
+        (_, true) => Isa::Sse3,
+        _ => Isa::Scalar
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn isa() -> Isa { Isa::Scalar }
+
+/// Inner loops of the FFT, spectral filtering and FIR convolution, picked at runtime per
+/// instruction set.
+pub trait Kernels: Sized {
+    /// `front[j] <- front[j] + back[j]`, `back[j] <- (front[j] - back[j]) * twiddles[j]`.
+    fn butterfly(front: &mut [Complex<Self>], back: &mut [Complex<Self>], twiddles: &[Complex<Self>]);
+
+    /// `dest[j] <- dest[j] * src[j]`.
+    fn complex_mul(dest: &mut [Complex<Self>], src: &[Complex<Self>]);
+
+    /// `dest[i] <- sum(filter[j] * padded[i + filter.len() - 1 - j])`, where `padded` carries
+    /// `filter.len() - 1` samples of history in front of the block.
+    fn fir(filter: &[Self], padded: &[Self], dest: &mut [Self]);
+}
+
+pub mod scalar {
+    use complex::Complex;
+    use float::Float;
+
+    pub fn butterfly<T: Float>(front: &mut [Complex<T>], back: &mut [Complex<T>], twiddles: &[Complex<T>]) {
+        for ((a, b), &w) in front.iter_mut().zip(back.iter_mut()).zip(twiddles.iter()) {
+            let (x, y) = (*a, *b);
+            *a = x + y;
+            *b = (x - y) * w;
+        }
+    }
+
+    pub fn complex_mul<T: Float>(dest: &mut [Complex<T>], src: &[Complex<T>]) {
+        for (d, &s) in dest.iter_mut().zip(src.iter()) {
+            *d *= s;
+        }
+    }
+
+    pub fn fir<T: Float>(filter: &[T], padded: &[T], dest: &mut [T]) {
+        let last = filter.len() - 1;
+        for (i, d) in dest.iter_mut().enumerate() {
+            *d = filter.iter().enumerate().map(|(j, &h)| h * padded[i + last - j]).sum();
+        }
+    }
+}
+
+fn check_lengths<T>(front: usize, back: usize, twiddles: &[T]) {
+    assert!(front == back && back <= twiddles.len(), "mismatched kernel operand lengths");
+}
+
+fn check_fir<T>(filter: &[T], padded: &[T], dest: &[T]) {
+    assert!(!filter.is_empty() && padded.len() + 1 >= dest.len() + filter.len(),
+        "fir history is shorter than the filter");
+}
+
+macro_rules! __dispatch {
+    ( $kernel:ident, $scalar:path, ( $( $arg:expr ),* ) ) => {{
+        #[cfg(target_arch = "x86_64")]
+        match isa() {
+            Isa::Avx => return unsafe { x86::avx::$kernel($( $arg ),*) },
+            Isa::Sse3 => return unsafe { x86::sse3::$kernel($( $arg ),*) },
+            Isa::Scalar => ()
+        }
+        $scalar($( $arg ),*)
+    }}
+}
+
+macro_rules! __kernels_impl {
+    ( $( $t:ident: $butterfly:ident, $complex_mul:ident, $fir:ident );+ ) => {$(
+        impl Kernels for $t {
+            fn butterfly(front: &mut [Complex<$t>], back: &mut [Complex<$t>], twiddles: &[Complex<$t>]) {
+                check_lengths(front.len(), back.len(), twiddles);
+                __dispatch!($butterfly, scalar::butterfly, (front, back, twiddles))
+            }
+
+            fn complex_mul(dest: &mut [Complex<$t>], src: &[Complex<$t>]) {
+                check_lengths(dest.len(), dest.len(), src);
+                __dispatch!($complex_mul, scalar::complex_mul, (dest, src))
+            }
+
+            fn fir(filter: &[$t], padded: &[$t], dest: &mut [$t]) {
+                check_fir(filter, padded, dest);
+                __dispatch!($fir, scalar::fir, (filter, padded, dest))
+            }
+        }
+    )+}
+}
+
+__kernels_impl! {
+    f32: butterfly_f32, complex_mul_f32, fir_f32;
+    f64: butterfly_f64, complex_mul_f64, fir_f64
+}
+
+fn split_tail(len: usize, lanes: usize) -> usize { len - len % lanes }
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    macro_rules! __complex_kernels {
+        ( $feature:tt, $t:ident, $lanes:expr, $load:ident, $store:ident, $add:ident, $sub:ident, $cmul:ident,
+            $butterfly:ident, $complex_mul:ident ) => {
+            #[target_feature(enable = $feature)]
+            pub unsafe fn $butterfly(front: &mut [Complex<$t>], back: &mut [Complex<$t>], twiddles: &[Complex<$t>]) {
+                let body = split_tail(front.len(), $lanes);
+                let (a, b, w) = (
+                    front.as_mut_ptr() as *mut $t,
+                    back.as_mut_ptr() as *mut $t,
+                    twiddles.as_ptr() as *const $t
+                );
+
+                for j in (0..body).step_by($lanes) {
+                    let (x, y, c) = ($load(a.add(2 * j)), $load(b.add(2 * j)), $load(w.add(2 * j)));
+                    $store(a.add(2 * j), $add(x, y));
+                    $store(b.add(2 * j), $cmul($sub(x, y), c));
+                }
+                scalar::butterfly(&mut front[body..], &mut back[body..], &twiddles[body..]);
+            }
+
+            #[target_feature(enable = $feature)]
+            pub unsafe fn $complex_mul(dest: &mut [Complex<$t>], src: &[Complex<$t>]) {
+                let body = split_tail(dest.len(), $lanes);
+                let (d, s) = (dest.as_mut_ptr() as *mut $t, src.as_ptr() as *const $t);
+
+                for j in (0..body).step_by($lanes) {
+                    $store(d.add(2 * j), $cmul($load(d.add(2 * j)), $load(s.add(2 * j))));
+                }
+                scalar::complex_mul(&mut dest[body..], &src[body..]);
+            }
+        }
+    }
+
+    macro_rules! __fir_kernel {
+        ( $feature:tt, $t:ident, $lanes:expr, $load:ident, $store:ident, $add:ident, $mul:ident, $set1:ident,
+            $zero:ident, $fir:ident ) => {
+            #[target_feature(enable = $feature)]
+            pub unsafe fn $fir(filter: &[$t], padded: &[$t], dest: &mut [$t]) {
+                let (body, last) = (split_tail(dest.len(), $lanes), filter.len() - 1);
+                let (x, d) = (padded.as_ptr(), dest.as_mut_ptr());
+
+                for i in (0..body).step_by($lanes) {
+                    let acc = filter.iter().enumerate().fold($zero(), |acc, (j, &h)| {
+                        $add(acc, $mul($set1(h), $load(x.add(i + last - j))))
+                    });
+                    $store(d.add(i), acc);
+                }
+                scalar::fir(filter, &padded[body..], &mut dest[body..]);
+            }
+        }
+    }
+
+    pub mod avx {
+        use std::arch::x86_64::*;
+        use complex::Complex;
+        use simd::{ scalar, split_tail };
+        use super::{ cmul_pd_avx, cmul_ps_avx };
+
+        __complex_kernels!("avx", f32, 4, _mm256_loadu_ps, _mm256_storeu_ps, _mm256_add_ps, _mm256_sub_ps,
+            cmul_ps_avx, butterfly_f32, complex_mul_f32);
+        __complex_kernels!("avx", f64, 2, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_add_pd, _mm256_sub_pd,
+            cmul_pd_avx, butterfly_f64, complex_mul_f64);
+        __fir_kernel!("avx", f32, 8, _mm256_loadu_ps, _mm256_storeu_ps, _mm256_add_ps, _mm256_mul_ps,
+            _mm256_set1_ps, _mm256_setzero_ps, fir_f32);
+        __fir_kernel!("avx", f64, 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_add_pd, _mm256_mul_pd,
+            _mm256_set1_pd, _mm256_setzero_pd, fir_f64);
+    }
+
+    pub mod sse3 {
+        use std::arch::x86_64::*;
+        use complex::Complex;
+        use simd::{ scalar, split_tail };
+        use super::{ cmul_pd_sse3, cmul_ps_sse3 };
+
+        __complex_kernels!("sse3", f32, 2, _mm_loadu_ps, _mm_storeu_ps, _mm_add_ps, _mm_sub_ps,
+            cmul_ps_sse3, butterfly_f32, complex_mul_f32);
+        __complex_kernels!("sse3", f64, 1, _mm_loadu_pd, _mm_storeu_pd, _mm_add_pd, _mm_sub_pd,
+            cmul_pd_sse3, butterfly_f64, complex_mul_f64);
+        __fir_kernel!("sse3", f32, 4, _mm_loadu_ps, _mm_storeu_ps, _mm_add_ps, _mm_mul_ps,
+            _mm_set1_ps, _mm_setzero_ps, fir_f32);
+        __fir_kernel!("sse3", f64, 2, _mm_loadu_pd, _mm_storeu_pd, _mm_add_pd, _mm_mul_pd,
+            _mm_set1_pd, _mm_setzero_pd, fir_f64);
+    }
+
+    // (xr + i xi)(yr + i yi) on interleaved lanes: addsub(x * yr, swap(x) * yi).
+    #[inline]
+    #[target_feature(enable = "avx")]
+    unsafe fn cmul_ps_avx(x: __m256, y: __m256) -> __m256 {
+        _mm256_addsub_ps(
+            _mm256_mul_ps(x, _mm256_moveldup_ps(y)),
+            _mm256_mul_ps(_mm256_permute_ps(x, 0xb1), _mm256_movehdup_ps(y))
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx")]
+    unsafe fn cmul_pd_avx(x: __m256d, y: __m256d) -> __m256d {
+        _mm256_addsub_pd(
+            _mm256_mul_pd(x, _mm256_movedup_pd(y)),
+            _mm256_mul_pd(_mm256_permute_pd(x, 0x5), _mm256_permute_pd(y, 0xf))
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse3")]
+    unsafe fn cmul_ps_sse3(x: __m128, y: __m128) -> __m128 {
+        _mm_addsub_ps(
+            _mm_mul_ps(x, _mm_moveldup_ps(y)),
+            _mm_mul_ps(_mm_shuffle_ps(x, x, 0xb1), _mm_movehdup_ps(y))
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse3")]
+    unsafe fn cmul_pd_sse3(x: __m128d, y: __m128d) -> __m128d {
+        _mm_addsub_pd(
+            _mm_mul_pd(x, _mm_movedup_pd(y)),
+            _mm_mul_pd(_mm_shuffle_pd(x, x, 0x1), _mm_unpackhi_pd(y, y))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Kernels, scalar };
+    use complex::Complex;
+    use float::Float;
+
+    fn signal<T: Float>(len: usize, seed: f64) -> Vec<Complex<T>> {
+        (0..len).map(|i| Complex::new(
+            T::from_f64((i as f64 * 0.61 + seed).sin()),
+            T::from_f64((i as f64 * 1.37 - seed).cos())
+        )).collect()
+    }
+
+    fn max_error<T: Float>(a: &[Complex<T>], b: &[Complex<T>]) -> f64 {
+        a.iter().zip(b.iter()).map(|(x, y)| (*x - *y).norm().to_f64()).fold(0.0, f64::max)
+    }
+
+    type Butterfly<T> = unsafe fn(&mut [Complex<T>], &mut [Complex<T>], &[Complex<T>]);
+    type ComplexMul<T> = unsafe fn(&mut [Complex<T>], &[Complex<T>]);
+    type Fir<T> = unsafe fn(&[T], &[T], &mut [T]);
+
+    // The kernels must be safe to call on this host.
+    fn check_complex_kernels<T: Float>(butterfly: Butterfly<T>, complex_mul: ComplexMul<T>, tolerance: f64) {
+        for &len in &[1, 2, 3, 7, 8, 13, 64, 67] {
+            let (front, back, twiddles) = (signal::<T>(len, 0.1), signal::<T>(len, 0.7), signal::<T>(len, 2.3));
+
+            let (mut f0, mut b0, mut f1, mut b1) = (front.clone(), back.clone(), front.clone(), back.clone());
+            scalar::butterfly(&mut f0, &mut b0, &twiddles);
+            unsafe { butterfly(&mut f1, &mut b1, &twiddles) };
+            assert!(max_error(&f0, &f1) < tolerance && max_error(&b0, &b1) < tolerance, "butterfly of {}", len);
+
+            let (mut d0, mut d1) = (front.clone(), front.clone());
+            scalar::complex_mul(&mut d0, &twiddles);
+            unsafe { complex_mul(&mut d1, &twiddles) };
+            assert!(max_error(&d0, &d1) < tolerance, "complex_mul of {}", len);
+        }
+    }
+
+    fn check_fir<T: Float>(fir: Fir<T>, tolerance: f64) {
+        for &(taps, len) in &[(1, 5), (3, 17), (8, 8), (31, 100), (5, 3)] {
+            let filter: Vec<T> = signal::<T>(taps, 0.4).iter().map(|c| c.re).collect();
+            let padded: Vec<T> = signal::<T>(len + taps - 1, 1.1).iter().map(|c| c.im).collect();
+
+            let (mut d0, mut d1) = (vec![T::zero(); len], vec![T::zero(); len]);
+            scalar::fir(&filter, &padded, &mut d0);
+            unsafe { fir(&filter, &padded, &mut d1) };
+            assert!(d0.iter().zip(d1.iter()).all(|(x, y)| (*x - *y).abs().to_f64() < tolerance), "fir of {}", len);
+        }
+    }
+
+    #[test]
+    fn test_dispatch_matches_scalar() {
+        check_complex_kernels::<f32>(f32::butterfly, f32::complex_mul, 1e-6);
+        check_complex_kernels::<f64>(f64::butterfly, f64::complex_mul, 1e-14);
+        check_fir::<f32>(f32::fir, 1e-5);
+        check_fir::<f64>(f64::fir, 1e-13);
+    }
+
+    /// Every kernel of each instruction set the host supports, whichever one dispatch picks.
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_x86_kernels_match_scalar() {
+        use super::x86::{ avx, sse3 };
+
+        macro_rules! check_isa {
+            ( $isa:ident ) => {
+                check_complex_kernels::<f32>($isa::butterfly_f32, $isa::complex_mul_f32, 1e-6);
+                check_complex_kernels::<f64>($isa::butterfly_f64, $isa::complex_mul_f64, 1e-14);
+                check_fir::<f32>($isa::fir_f32, 1e-5);
+                check_fir::<f64>($isa::fir_f64, 1e-13);
+            }
+        }
+
+        if is_x86_feature_detected!("sse3") {
+            check_isa!(sse3);
+        }
+        if is_x86_feature_detected!("avx") {
+            check_isa!(avx);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+use std::path::Path;
+use byteorder::{ BigEndian, LittleEndian, WriteBytesExt };
+use complex::Complex;
+use stft::Stft;
+use Wave;
+
+/// Magnitudes of the non-negative frequency bins of each STFT frame.
+pub struct Spectrogram {
+    pub frames: Vec<Vec<f32>>,
+    pub sample_rate: u32,
+    pub fft_len: usize
+}
+
+impl Spectrogram {
+    pub fn from_frames(frames: &[Vec<Complex<f32>>], sample_rate: u32) -> Self {
+        let fft_len = frames.first().map_or(0, Vec::len);
+        let frames = frames.iter()
+            .map(|f| f.iter().take(fft_len / 2 + 1).map(Complex::norm).collect())
+            .collect();
+        Spectrogram { frames, sample_rate, fft_len }
+    }
+
+    pub fn from_wave(wave: &Wave, stft: &Stft<f32>) -> Self {
+        Spectrogram::from_frames(&stft.analyze(&wave.data), wave.format.sample_rate)
+    }
+
+    fn bin_width(&self) -> f32 { self.sample_rate as f32 / self.fft_len as f32 }
+
+    /// Magnitude at an arbitrary frequency, linearly interpolated between bins.
+    fn magnitude_at(&self, frame: usize, freq: f32) -> f32 {
+        let bins = &self.frames[frame];
+        let pos = (freq / self.bin_width()).max(0.0).min((bins.len() - 1) as f32);
+        let (lo, frac) = (pos.floor() as usize, pos - pos.floor());
+        match bins.get(lo + 1) {
+            Some(&hi) => bins[lo] * (1.0 - frac) + hi * frac,
+            None => bins[lo]
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrequencyScale {
+    Linear,
+    /// Logarithmic from the given lowest frequency in Hz up to Nyquist.
+    Log(f32)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Colormap {
+    Gray,
+    Hot,
+    Viridis
+}
+
+const HOT: [[f32; 3]; 4] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0]];
+const VIRIDIS: [[f32; 3]; 5] = [
+    [0.267, 0.005, 0.329], [0.229, 0.322, 0.546], [0.128, 0.567, 0.551],
+    [0.369, 0.789, 0.383], [0.993, 0.906, 0.144]
+];
+
+fn interpolate(stops: &[[f32; 3]], level: f32) -> [u8; 3] {
+    let pos = level * (stops.len() - 1) as f32;
+    let lo = (pos.floor() as usize).min(stops.len() - 2);
+    let frac = pos - lo as f32;
+    let mut rgb = [0; 3];
+    for (c, v) in rgb.iter_mut().enumerate() {
+        *v = ((stops[lo][c] * (1.0 - frac) + stops[lo + 1][c] * frac) * 255.0).round() as u8;
+    }
+    rgb
+}
+
+impl Colormap {
+    /// Colour for a level in `[0, 1]`.
+    pub fn color(&self, level: f32) -> [u8; 3] {
+        let level = level.clamp(0.0, 1.0);
+        match *self {
+            Colormap::Gray => [(level * 255.0).round() as u8; 3],
+            Colormap::Hot => interpolate(&HOT, level),
+            Colormap::Viridis => interpolate(&VIRIDIS, level)
+        }
+    }
+}
+
+/// Renders a `Spectrogram` with one column per frame and `height` rows, low
+/// frequencies at the bottom. Levels are in dB relative to the loudest bin and
+/// clipped `range_db` below it.
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    pub height: usize,
+    pub range_db: f32,
+    pub scale: FrequencyScale,
+    pub colormap: Colormap
+}
+
+impl Renderer {
+    pub fn new(height: usize) -> Self {
+        Renderer { height, range_db: 80.0, scale: FrequencyScale::Linear, colormap: Colormap::Gray }
+    }
+
+    pub fn with_range_db(mut self, range_db: f32) -> Self {
+        assert!(range_db > 0.0, "dynamic range must be positive, got {}", range_db);
+        self.range_db = range_db;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: FrequencyScale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    /// Centre frequency in Hz of image row `row`, counted from the bottom.
+    pub fn row_frequency(&self, row: usize, sample_rate: u32) -> f32 {
+        let (nyquist, pos) = (sample_rate as f32 / 2.0, (row as f32 + 0.5) / self.height as f32);
+        match self.scale {
+            FrequencyScale::Linear => nyquist * pos,
+            FrequencyScale::Log(min) => min * (nyquist / min).powf(pos)
+        }
+    }
+
+    pub fn render(&self, spectrogram: &Spectrogram) -> Image {
+        let width = spectrogram.frames.len();
+        let db = |m: f32| 20.0 * m.max(1e-20).log10();
+        let peak = spectrogram.frames.iter().flat_map(|f| f.iter()).cloned().fold(0.0, f32::max);
+        let top = db(peak);
+
+        let freqs: Vec<_> = (0..self.height).rev().map(|r| self.row_frequency(r, spectrogram.sample_rate)).collect();
+        let mut pixels = Vec::with_capacity(width * self.height);
+        for &freq in &freqs {
+            for t in 0..width {
+                let level = (db(spectrogram.magnitude_at(t, freq)) - top + self.range_db) / self.range_db;
+                pixels.push(self.colormap.color(level));
+            }
+        }
+        Image { width, height: self.height, pixels }
+    }
+}
+
+/// RGB image in row-major order, top row first.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>
+}
+
+fn luma(rgb: &[u8; 3]) -> u8 {
+    (0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32).round() as u8
+}
+
+fn crc32(chunks: &[&[u8]]) -> u32 {
+    let table: Vec<u32> = (0..256u32).map(|n| (0..8).fold(n, |c, _| match c & 1 {
+        1 => 0xedb8_8320 ^ (c >> 1),
+        _ => c >> 1
+    })).collect();
+    !chunks.iter().flat_map(|c| c.iter())
+        .fold(!0u32, |c, &b| table[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &x| {
+        let a = (a + x as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8], data: &[u8]) -> io::Result<()> {
+    w.write_u32::<BigEndian>(data.len() as u32)?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_u32::<BigEndian>(crc32(&[kind, data]))
+}
+
+impl Image {
+    pub fn write_pgm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P5\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.pixels.iter().map(luma).collect::<Vec<_>>())
+    }
+
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.pixels.iter().flat_map(|p| p.iter().cloned()).collect::<Vec<_>>())
+    }
+
+    /// Truecolour PNG with uncompressed (stored) deflate blocks.
+    pub fn write_png<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut raw = Vec::with_capacity(self.height * (3 * self.width + 1));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            raw.push(0);
+            raw.extend(row.iter().flat_map(|p| p.iter().cloned()));
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let blocks: Vec<_> = raw.chunks(0xffff).collect();
+        for (i, block) in blocks.iter().enumerate() {
+            zlib.push((i + 1 == blocks.len()) as u8);
+            zlib.write_u16::<LittleEndian>(block.len() as u16)?;
+            zlib.write_u16::<LittleEndian>(!(block.len() as u16))?;
+            zlib.extend_from_slice(block);
+        }
+        if blocks.is_empty() {
+            zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        }
+        zlib.write_u32::<BigEndian>(adler32(&raw))?;
+
+        let mut header = Vec::with_capacity(13);
+        header.write_u32::<BigEndian>(self.width as u32)?;
+        header.write_u32::<BigEndian>(self.height as u32)?;
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(w, b"IHDR", &header)?;
+        write_chunk(w, b"IDAT", &zlib)?;
+        write_chunk(w, b"IEND", &[])
+    }
+
+    /// Writes the image in the format given by the file extension: `pgm`, `ppm` or `png`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let ext = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+        let mut w = BufWriter::new(File::create(path)?);
+        match ext.as_deref() {
+            Some("pgm") => self.write_pgm(&mut w),
+            Some("ppm") => self.write_ppm(&mut w),
+            Some("png") => self.write_png(&mut w),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "expected a .pgm, .ppm or .png path"))
+        }?;
+        w.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Colormap, FrequencyScale, Renderer, Spectrogram, adler32, crc32 };
+    use stft::Stft;
+    use synth::{ mix, sine };
+    use Wave;
+
+    const RATE: u32 = 8000;
+
+    fn spectrogram() -> Spectrogram {
+        let src = mix(&[sine(500.0, 0.5, RATE, 8000), sine(3500.0, 0.05, RATE, 8000)]);
+        let window: Vec<f32> = (0..256).map(|n| (::std::f32::consts::PI * (n as f32 + 0.5) / 256.0).sin().powi(2)).collect();
+        Spectrogram::from_wave(&Wave::from_samples(src, RATE), &Stft::new(window, 128))
+    }
+
+    fn brightest_row(renderer: &Renderer, spectrogram: &Spectrogram, column: usize) -> usize {
+        let image = renderer.render(spectrogram);
+        let bottom_up = |r: usize| image.pixels[(image.height - 1 - r) * image.width + column][0];
+        (0..image.height).max_by_key(|&r| bottom_up(r)).unwrap()
+    }
+
+    #[test]
+    fn test_render_axes() {
+        let spectrogram = spectrogram();
+        assert_eq!(spectrogram.frames[0].len(), 129);
+
+        for &scale in &[FrequencyScale::Linear, FrequencyScale::Log(50.0)] {
+            let renderer = Renderer::new(200).with_scale(scale);
+            let row = brightest_row(&renderer, &spectrogram, 30);
+            assert!((renderer.row_frequency(row, RATE) - 500.0).abs() < 40.0, "{:?}: row {}", scale, row);
+        }
+    }
+
+    #[test]
+    fn test_dynamic_range() {
+        let spectrogram = spectrogram();
+        let row = |renderer: &Renderer| {
+            let image = renderer.render(&spectrogram);
+            let r = (0..image.height).find(|&r| (renderer.row_frequency(r, RATE) - 3500.0).abs() < 10.0).unwrap();
+            image.pixels[(image.height - 1 - r) * image.width + 30][0]
+        };
+
+        // The 3500 Hz tone sits 20 dB below the peak.
+        let (wide, narrow) = (row(&Renderer::new(400)), row(&Renderer::new(400).with_range_db(20.0)));
+        assert!(wide > 150 && wide < 215, "{}", wide);
+        assert!(narrow < 30, "{}", narrow);
+    }
+
+    #[test]
+    fn test_colormaps() {
+        assert_eq!(Colormap::Gray.color(0.5), [128; 3]);
+        assert_eq!(Colormap::Hot.color(0.0), [0, 0, 0]);
+        assert_eq!(Colormap::Hot.color(1.0), [255, 255, 255]);
+        assert_eq!(Colormap::Viridis.color(2.0), Colormap::Viridis.color(1.0));
+    }
+
+    #[test]
+    fn test_encoders() {
+        assert_eq!(crc32(&[b"IEND"]), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        let image = Renderer::new(16).with_colormap(Colormap::Hot).render(&spectrogram());
+        let (mut pgm, mut ppm, mut png) = (Vec::new(), Vec::new(), Vec::new());
+        image.write_pgm(&mut pgm).unwrap();
+        image.write_ppm(&mut ppm).unwrap();
+        image.write_png(&mut png).unwrap();
+
+        let header = format!("P5\n{} 16\n255\n", image.width);
+        assert!(pgm.starts_with(header.as_bytes()));
+        assert_eq!(pgm.len(), header.len() + image.width * 16);
+        assert_eq!(ppm.len(), header.len() + image.width * 16 * 3);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+}
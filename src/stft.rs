@@ -0,0 +1,282 @@
+use complex::Complex;
+use float::Float;
+use { fft_complex, ifft };
+
+/// Short-time Fourier transform with an arbitrary analysis window and hop size.
+///
+/// The signal is padded with `window.len() - hop` zeros in front and as many as needed
+/// at the end, so the first and last samples are covered by as many frames as any other
+/// and the trailing partial frame is kept. Frames are zero-padded to `fft_len`.
+#[derive(Clone, Debug)]
+pub struct Stft<T> {
+    pub window: Vec<T>,
+    pub hop: usize,
+    pub fft_len: usize
+}
+
+fn front_pad(window_len: usize, hop: usize) -> usize { window_len.saturating_sub(hop) }
+
+impl<T: Float> Stft<T> {
+    pub fn new(window: Vec<T>, hop: usize) -> Self {
+        assert!(hop > 0 && !window.is_empty(), "stft needs a window and a positive hop");
+        let fft_len = window.len().next_power_of_two();
+        Stft { window, hop, fft_len }
+    }
+
+    pub fn with_fft_len(mut self, fft_len: usize) -> Self {
+        assert!(fft_len.is_power_of_two(), "fft length must be a power of two, got {}", fft_len);
+        assert!(fft_len >= self.window.len(), "fft length {} is shorter than the window", fft_len);
+        self.fft_len = fft_len;
+        self
+    }
+
+    pub fn frame_count(&self, len: usize) -> usize {
+        (len + front_pad(self.window.len(), self.hop)).div_ceil(self.hop)
+    }
+
+    /// Offset in samples of the first sample of frame `index`, negative inside the padding.
+    pub fn frame_start(&self, index: usize) -> isize {
+        (index * self.hop) as isize - front_pad(self.window.len(), self.hop) as isize
+    }
+
+    /// Time in seconds of the centre of frame `index`.
+    pub fn frame_time(&self, index: usize, sample_rate: u32) -> f32 {
+        (self.frame_start(index) as f32 + self.window.len() as f32 / 2.0) / sample_rate as f32
+    }
+
+    pub fn bin_frequency(&self, bin: usize, sample_rate: u32) -> f32 {
+        bin as f32 * sample_rate as f32 / self.fft_len as f32
+    }
+
+    pub fn frames<'a>(&'a self, src: &'a [T]) -> StftFrames<'a, T> {
+        StftFrames { stft: self, src, index: 0, count: self.frame_count(src.len()) }
+    }
+
+    pub fn analyze(&self, src: &[T]) -> Vec<Vec<Complex<T>>> {
+        self.frames(src).collect()
+    }
+
+    fn frame(&self, src: &[T], index: usize) -> Vec<Complex<T>> {
+        let start = self.frame_start(index);
+        let mut frame: Vec<_> = self.window.iter().enumerate().map(|(n, &w)| {
+            let sample = match start + n as isize {
+                i if i >= 0 && (i as usize) < src.len() => src[i as usize],
+                _ => T::zero()
+            };
+            Complex::new(sample * w, T::zero())
+        }).collect();
+        frame.resize(self.fft_len, Complex::default());
+        fft_complex(frame)
+    }
+}
+
+pub struct StftFrames<'a, T: 'a> {
+    stft: &'a Stft<T>,
+    src: &'a [T],
+    index: usize,
+    count: usize
+}
+
+impl<'a, T: Float> Iterator for StftFrames<'a, T> {
+    type Item = Vec<Complex<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.index {
+            i if i < self.count => {
+                self.index += 1;
+                Some(self.stft.frame(self.src, i))
+            },
+            _ => None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Returns the constant sum of `window` overlapped at `hop`, or `None` if the window
+/// does not satisfy the constant overlap-add condition.
+pub fn cola<T: Float>(window: &[T], hop: usize) -> Option<T> {
+    let sums: Vec<T> = (0..hop).map(|n| window.iter().skip(n).step_by(hop).cloned().sum()).collect();
+    let (lo, hi) = sums.iter().fold((T::INFINITY, T::NEG_INFINITY), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+    match hi - lo {
+        d if d <= T::from_f64(1e-6) * hi.abs() && hi > T::zero() => Some(hi),
+        _ => None
+    }
+}
+
+/// Inverse of `Stft` by weighted overlap-add. Each output sample is divided by the sum
+/// of analysis times synthesis window over the frames covering it, so reconstruction is
+/// exact for any window and hop whose frames cover every sample.
+#[derive(Clone, Debug)]
+pub struct Istft<T> {
+    pub analysis: Vec<T>,
+    pub synthesis: Vec<T>,
+    pub hop: usize,
+    pub fft_len: usize
+}
+
+impl<T: Float> Istft<T> {
+    pub fn new(window: Vec<T>, hop: usize) -> Self {
+        Istft::from_stft(&Stft::new(window, hop))
+    }
+
+    pub fn from_stft(stft: &Stft<T>) -> Self {
+        Istft {
+            analysis: stft.window.clone(),
+            synthesis: stft.window.clone(),
+            hop: stft.hop,
+            fft_len: stft.fft_len
+        }
+    }
+
+    pub fn with_fft_len(mut self, fft_len: usize) -> Self {
+        assert!(fft_len.is_power_of_two(), "fft length must be a power of two, got {}", fft_len);
+        assert!(fft_len >= self.analysis.len() && fft_len >= self.synthesis.len(),
+            "fft length {} is shorter than the windows", fft_len);
+        self.fft_len = fft_len;
+        self
+    }
+
+    /// Replaces the synthesis window, which may be up to `fft_len` long. A window of
+    /// ones over the whole FFT frame turns this into plain overlap-add, as used for fast
+    /// convolution.
+    pub fn with_synthesis_window(mut self, window: Vec<T>) -> Self {
+        assert!(window.len() <= self.fft_len, "synthesis window is longer than the fft");
+        self.synthesis = window;
+        self
+    }
+
+    /// Whether analysis times synthesis window overlap-adds to a constant.
+    pub fn is_cola(&self) -> bool {
+        let at = |w: &[T], n: usize| w.get(n).cloned().unwrap_or_default();
+        let product: Vec<_> = (0..self.synthesis.len().max(self.analysis.len()))
+            .map(|n| at(&self.analysis, n) * at(&self.synthesis, n)).collect();
+        cola(&product, self.hop).is_some()
+    }
+
+    pub fn synthesize(&self, frames: &[Vec<Complex<T>>], len: usize) -> Vec<T> {
+        let pad = front_pad(self.analysis.len(), self.hop);
+        let total = frames.len() * self.hop + self.fft_len;
+        let (mut out, mut norm) = (vec![T::zero(); total], vec![T::zero(); total]);
+
+        for (t, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.len(), self.fft_len, "frame {} does not hold fft_len bins", t);
+            let offset = t * self.hop;
+            for (n, (y, &s)) in ifft(frame.clone()).into_iter().zip(self.synthesis.iter()).enumerate() {
+                out[offset + n] += y.re * s;
+                norm[offset + n] += self.analysis.get(n).cloned().unwrap_or_default() * s;
+            }
+        }
+
+        let floor = T::from_f64(1e-10);
+        (pad..pad + len).map(|n| match n < total {
+            true if norm[n].abs() > floor => out[n] / norm[n],
+            _ => T::zero()
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Istft, Stft, cola };
+    use dct::{ kbd_window, sine_window };
+    use window::{ Symmetry, hann };
+
+    fn signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| (i as f64 * 0.13).sin() + 0.3 * (i as f64 * 1.7).cos() + ((i * 31 % 17) as f64 - 8.0) / 40.0)
+            .collect()
+    }
+
+    fn assert_reconstructs(stft: Stft<f64>, istft: Istft<f64>, len: usize) {
+        let src = signal(len);
+        let frames = stft.analyze(&src);
+        assert_eq!(frames.len(), stft.frame_count(len));
+        assert!(frames.iter().all(|f| f.len() == stft.fft_len));
+
+        let res = istft.synthesize(&frames, len);
+        for (i, (x, y)) in res.iter().zip(src.iter()).enumerate() {
+            assert!((x - y).abs() < 1e-9, "sample {}: {} != {}", i, x, y);
+        }
+    }
+
+    #[test]
+    fn test_reconstruction() {
+        let windows: Vec<(Vec<f64>, usize)> = vec![
+            (sine_window(256), 128),
+            (sine_window(256), 64),
+            (kbd_window(128, 4.0), 64),
+            (vec![1.0; 100], 100),
+            (vec![1.0; 100], 37),
+            (sine_window(200), 75)
+        ];
+
+        for (window, hop) in windows {
+            let stft = Stft::new(window, hop);
+            let istft = Istft::from_stft(&stft);
+            assert_reconstructs(stft, istft, 1001);
+        }
+    }
+
+    #[test]
+    fn test_zero_padded_fft() {
+        let stft = Stft::new(sine_window(100), 50).with_fft_len(512);
+        let istft = Istft::from_stft(&stft);
+        assert_eq!(istft.fft_len, 512);
+        assert_reconstructs(stft, istft, 777);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be a power of two")]
+    fn test_fft_len_not_power_of_two() {
+        Stft::new(sine_window::<f64>(256), 128).with_fft_len(300);
+    }
+
+    #[test]
+    #[should_panic(expected = "shorter than the windows")]
+    fn test_istft_fft_len_shorter_than_window() {
+        Istft::new(sine_window::<f64>(256), 128).with_fft_len(128);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not hold fft_len bins")]
+    fn test_istft_frame_longer_than_fft() {
+        let stft = Stft::new(sine_window::<f64>(64), 32).with_fft_len(128);
+        let frames = stft.analyze(&[0.5; 200]);
+        Istft::from_stft(&stft).with_fft_len(64).synthesize(&frames, 200);
+    }
+
+    #[test]
+    fn test_fast_convolution_layout() {
+        let stft = Stft::new(vec![1.0; 128], 128).with_fft_len(256);
+        let istft = Istft::from_stft(&stft).with_synthesis_window(vec![1.0; 256]);
+        assert!(istft.is_cola());
+        assert_reconstructs(stft, istft, 1000);
+    }
+
+    #[test]
+    fn test_edges_and_trailing_frame() {
+        let stft = Stft::new(vec![1.0f64; 64], 64);
+        assert_eq!(stft.frame_count(1000), 16);
+        assert_eq!(stft.frame_start(0), 0);
+
+        let overlapped = Stft::new(sine_window::<f64>(64), 16);
+        assert_eq!(overlapped.frame_start(0), -48);
+        assert_eq!(overlapped.frame_count(100), 10);
+        assert!((overlapped.frame_time(3, 16) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cola() {
+        let hann = hann::<f64>(256, Symmetry::Periodic);
+
+        assert!((cola(&hann, 128).unwrap() - 1.0).abs() < 1e-9);
+        assert!((cola(&hann, 64).unwrap() - 2.0).abs() < 1e-9);
+        assert!(cola(&hann, 100).is_none());
+        assert!(cola(&vec![1.0f64; 90], 30).is_some());
+        assert!(Istft::new(sine_window::<f64>(256), 128).is_cola());
+        assert!(!Istft::new(sine_window::<f64>(256), 100).is_cola());
+    }
+}
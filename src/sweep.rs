@@ -0,0 +1,232 @@
+use complex::Complex;
+use float::Float;
+use goertzel::goertzel_generalized;
+use { fft_complex, ifft };
+use Wave;
+
+/// Exponential sine sweep from `start` to `end` Hz over `len` samples, after Farina.
+/// Convolving a recording of the sweep through a system with `inverse_filter` gives the
+/// system's linear impulse response, preceded by one response per harmonic order.
+#[derive(Clone, Debug)]
+pub struct Sweep<T> {
+    pub start: T,
+    pub end: T,
+    pub amplitude: T,
+    pub sample_rate: u32,
+    pub len: usize
+}
+
+impl<T: Float> Sweep<T> {
+    pub fn new(start: T, end: T, len: usize, sample_rate: u32) -> Self {
+        assert!(start > T::zero() && end > start, "sweep must rise from a positive frequency");
+        Sweep { start, end, amplitude: T::one(), sample_rate, len }
+    }
+
+    pub fn with_amplitude(mut self, amplitude: T) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Sweep duration over `ln(end / start)`, in samples.
+    fn rate(&self) -> T {
+        T::from_usize(self.len) / (self.end / self.start).ln()
+    }
+
+    fn frequency(&self, n: usize) -> T {
+        self.start * (T::from_usize(n) / self.rate()).exp()
+    }
+
+    pub fn signal(&self) -> Vec<T> {
+        let (rate, fs) = (self.rate(), T::from_f64(self.sample_rate as f64));
+        (0..self.len).map(|n| {
+            let phase = T::from_f64(2.0) * T::PI * self.start * rate / fs * ((T::from_usize(n) / rate).exp() - T::one());
+            self.amplitude * phase.sin()
+        }).collect()
+    }
+
+    /// Time reversed sweep fading by 6 dB per octave as it falls, scaled so that it
+    /// convolved with `signal` has unit gain within the sweep's band.
+    pub fn inverse_filter(&self) -> Vec<T> {
+        let signal = self.signal();
+        let inverse: Vec<T> = (0..self.len).map(|n| {
+            let m = self.len - 1 - n;
+            signal[m] * self.frequency(m) / self.end
+        }).collect();
+
+        // Averaged over the middle half of the band in log frequency, clear of the
+        // ripple near the sweep's ends.
+        let points = 32;
+        let gain = (0..points).map(|i| {
+            let position = T::from_f64(0.25 + 0.5 * i as f64 / (points - 1) as f64);
+            let freq = self.start * (self.end / self.start).powf(position);
+            let bin = freq * T::from_usize(self.len) / T::from_f64(self.sample_rate as f64);
+            goertzel_generalized(&signal, bin).norm() * goertzel_generalized(&inverse, bin).norm()
+        }).sum::<T>() / T::from_usize(points);
+        inverse.into_iter().map(|x| x / gain).collect()
+    }
+
+    /// Full linear deconvolution of `recorded`. The linear response starts at index
+    /// `len - 1`, with harmonic order `k` `harmonic_delay(k)` samples before it.
+    pub fn deconvolve(&self, recorded: &[T]) -> Vec<T> {
+        let out = recorded.len() + self.len - 1;
+        let size = out.next_power_of_two();
+        let spectrum = |s: Vec<T>| {
+            let mut v: Vec<_> = s.into_iter().map(|x| Complex::new(x, T::zero())).collect();
+            v.resize(size, Complex::default());
+            fft_complex(v)
+        };
+        let product = spectrum(recorded.to_vec()).into_iter().zip(spectrum(self.inverse_filter())).map(|(a, b)| a * b).collect();
+        ifft(product).into_iter().take(out).map(|c| c.re).collect()
+    }
+
+    /// How far the response of harmonic `order` leads the linear response, in samples.
+    pub fn harmonic_delay(&self, order: usize) -> T {
+        self.rate() * T::from_usize(order).ln()
+    }
+
+    /// The first `ir_len` samples of the linear impulse response.
+    pub fn impulse_response(&self, recorded: &[T], ir_len: usize) -> Vec<T> {
+        self.harmonic_responses(recorded, 1, ir_len).remove(0)
+    }
+
+    /// Impulse responses of harmonic orders `1..=orders`, each `ir_len` samples long.
+    /// Orders are cut at their rounded delay, so `ir_len` should stay below the gap
+    /// to the next order, `harmonic_delay(orders) - harmonic_delay(orders - 1)`.
+    pub fn harmonic_responses(&self, recorded: &[T], orders: usize, ir_len: usize) -> Vec<Vec<T>> {
+        let full = self.deconvolve(recorded);
+        (1..orders + 1).map(|order| {
+            let start = (self.len - 1) as isize - self.harmonic_delay(order).round().to_f64() as isize;
+            (0..ir_len as isize).map(|n| match start + n {
+                i if i >= 0 && (i as usize) < full.len() => full[i as usize],
+                _ => T::zero()
+            }).collect()
+        }).collect()
+    }
+}
+
+impl Sweep<f32> {
+    pub fn signal_wave(&self) -> Wave {
+        Wave::from_samples(self.signal(), self.sample_rate)
+    }
+
+    pub fn measure_wave(&self, recorded: &Wave, ir_len: usize) -> Wave {
+        assert_eq!(recorded.format.sample_rate, self.sample_rate, "recording and sweep sample rates differ");
+        Wave::from_samples(self.impulse_response(&recorded.data, ir_len), self.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sweep;
+    use complex::Complex;
+    use window::{ Symmetry, hann };
+    use { apply_fir, fft, fir_lpf, iir_lpf };
+
+    const RATE: u32 = 48000;
+    const IR_LEN: usize = 4096;
+    const PRE: usize = 1024;
+
+    fn sweep() -> Sweep<f64> {
+        Sweep::new(20.0, 20000.0, RATE as usize, RATE).with_amplitude(0.5)
+    }
+
+    /// The sweep followed by room for the response's tail.
+    fn excitation(sweep: &Sweep<f64>) -> Vec<f64> {
+        let mut src = sweep.signal();
+        src.resize(sweep.len + IR_LEN, 0.0);
+        src
+    }
+
+    fn biquad(((_, a1, a2), (b0, b1, b2)): ((f64, f64, f64), (f64, f64, f64)), src: &[f64]) -> Vec<f64> {
+        let mut dest: Vec<f64> = Vec::with_capacity(src.len());
+        for n in 0..src.len() {
+            let (x, y) = (|k: usize| if n >= k { src[n - k] } else { 0.0 }, |k: usize| if n >= k { dest[n - k] } else { 0.0 });
+            let out = b0 * x(0) + b1 * x(1) + b2 * x(2) - a1 * y(1) - a2 * y(2);
+            dest.push(out);
+        }
+        dest
+    }
+
+    /// `IR_LEN` samples of the deconvolution from `PRE` samples before `start`, keeping
+    /// the band-limited pulse's pre-ringing.
+    fn response(full: &[f64], start: usize) -> Vec<f64> {
+        full[start - PRE..][..IR_LEN].to_vec()
+    }
+
+    /// Compares the measured magnitude response with `expected` in dB between 100 Hz
+    /// and 15 kHz, wherever `expected` is above -40 dB.
+    fn assert_response<F: Fn(f64) -> Complex<f64>>(response: &[f64], expected: F, tolerance: f64) {
+        let width = RATE as f64 / response.len() as f64;
+        let bins = (100.0 / width) as usize..(15000.0 / width) as usize;
+        for (k, measured) in fft(response.to_vec()).into_iter().enumerate().take(bins.end).skip(bins.start) {
+            let truth = expected(k as f64 * width).db();
+            if truth > -40.0 {
+                assert!((measured.db() - truth).abs() < tolerance, "{} Hz: {} != {}", k as f64 * width, measured.db(), truth);
+            }
+        }
+    }
+
+    #[test]
+    fn test_identity() {
+        let sweep = sweep();
+        let recorded = excitation(&sweep);
+        let ir = sweep.impulse_response(&recorded, IR_LEN);
+        let peak = (0..ir.len()).fold(0, |best, i| if ir[i].abs() > ir[best].abs() { i } else { best });
+        assert_eq!(peak, 0);
+        // A pulse band-limited to 20 Hz - 20 kHz peaks at about 19980 / 24000.
+        assert!((ir[0] - 0.8325).abs() < 0.01, "{}", ir[0]);
+        assert_response(&response(&sweep.deconvolve(&recorded), sweep.len - 1), |_| Complex::new(1.0, 0.0), 0.1);
+    }
+
+    #[test]
+    fn test_fir_lpf() {
+        let sweep = sweep();
+        let filter = fir_lpf(0.1, 64, hann(65, Symmetry::Symmetric));
+        let recorded = apply_fir(&filter, &excitation(&sweep));
+
+        assert_response(&response(&sweep.deconvolve(&recorded), sweep.len - 1), |f| {
+            filter.iter().enumerate().fold(Complex::default(), |acc, (n, &h)| {
+                acc + Complex::from_polar(h, -2.0 * ::std::f64::consts::PI * f * n as f64 / RATE as f64)
+            })
+        }, 0.2);
+
+        // The filter passes nothing above 20 kHz, so the sweep's band covers it.
+        let ir = sweep.impulse_response(&recorded, IR_LEN);
+        for (n, &h) in filter.iter().enumerate() {
+            assert!((ir[n] - h).abs() < 1e-3, "{}: {} != {}", n, ir[n], h);
+        }
+    }
+
+    #[test]
+    fn test_iir_lpf() {
+        let sweep = sweep();
+        let params = iir_lpf(1000.0 / RATE as f64, 1.0 / 2f64.sqrt());
+        let recorded = biquad(params, &excitation(&sweep));
+
+        let ((_, a1, a2), (b0, b1, b2)) = params;
+        assert_response(&response(&sweep.deconvolve(&recorded), sweep.len - 1), |f| {
+            let z = |k: f64| Complex::from_polar(1.0, -2.0 * ::std::f64::consts::PI * f * k / RATE as f64);
+            (z(0.0) * b0 + z(1.0) * b1 + z(2.0) * b2) / (z(0.0) + z(1.0) * a1 + z(2.0) * a2)
+        }, 0.2);
+    }
+
+    #[test]
+    fn test_harmonic_separation() {
+        // y = x + c x^2 adds a second harmonic of amplitude c A^2 / 2, which relative to
+        // the sweep amplitude A reads c A / 2.
+        let (sweep, c) = (sweep(), 0.2);
+        let recorded: Vec<f64> = excitation(&sweep).into_iter().map(|x| x + c * x * x).collect();
+        let (full, responses) = (sweep.deconvolve(&recorded), sweep.harmonic_responses(&recorded, 3, 2048));
+
+        let level = |order: usize, freq: f64| {
+            let start = sweep.len - 1 - sweep.harmonic_delay(order).round() as usize;
+            assert_eq!(&full[start..start + 2048], &responses[order - 1][..]);
+            fft(response(&full, start))[(freq * IR_LEN as f64 / RATE as f64).round() as usize].norm()
+        };
+        assert!((level(1, 1000.0) - 1.0).abs() < 0.01, "{}", level(1, 1000.0));
+        assert!((level(2, 2000.0) - c * 0.5 / 2.0).abs() < 1e-3, "{}", level(2, 2000.0));
+        // No third harmonic; a full window here would reach back into the second's.
+        let peak = |ir: &[f64]| ir.iter().fold(0.0f64, |m, x| m.max(x.abs()));
+        assert!(peak(&responses[2]) < 0.01 * peak(&responses[1]));
+    }
+}
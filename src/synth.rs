@@ -0,0 +1,45 @@
+use float::Float;
+
+fn phase<T: Float>(freq: T, idx: usize, sample_rate: u32) -> T {
+    T::from_f64(2.0) * T::PI * freq * T::from_usize(idx) / T::from_f64(sample_rate as f64)
+}
+
+pub fn sine<T: Float>(freq: T, amplitude: T, sample_rate: u32, len: usize) -> Vec<T> {
+    (0..len).map(|i| amplitude * phase(freq, i, sample_rate).sin()).collect()
+}
+
+/// Harmonic tone with `amplitudes[k]` as the amplitude of partial `k + 1`.
+pub fn harmonics<T: Float>(freq: T, amplitudes: &[T], sample_rate: u32, len: usize) -> Vec<T> {
+    (0..len).map(|i| amplitudes.iter().enumerate()
+        .map(|(k, &a)| a * phase(freq * T::from_usize(k + 1), i, sample_rate).sin()).sum()).collect()
+}
+
+/// Sums signals sample by sample, treating the shorter ones as zero padded.
+pub fn mix<T: Float>(sources: &[Vec<T>]) -> Vec<T> {
+    let len = sources.iter().map(Vec::len).max().unwrap_or(0);
+    (0..len).map(|i| sources.iter().filter_map(|s| s.get(i).cloned()).sum()).collect()
+}
+
+pub fn silence<T: Float>(len: usize) -> Vec<T> {
+    vec![T::zero(); len]
+}
+
+/// Uniform white noise in `[-amplitude, amplitude)` from a linear congruential generator,
+/// so the same `seed` always gives the same signal.
+pub fn noise<T: Float>(amplitude: T, len: usize, seed: u64) -> Vec<T> {
+    (0..len).scan(seed, |state, _| {
+        *state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let x = (*state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0;
+        Some(amplitude * T::from_f64(x))
+    }).collect()
+}
+
+/// Linear sweep from `start` to `end` Hz over `len` samples.
+pub fn chirp<T: Float>(start: T, end: T, amplitude: T, sample_rate: u32, len: usize) -> Vec<T> {
+    let (rate, duration) = (T::from_f64(sample_rate as f64), T::from_usize(len) / T::from_f64(sample_rate as f64));
+    let rate_of_change = (end - start) / duration;
+    (0..len).map(|i| {
+        let t = T::from_usize(i) / rate;
+        amplitude * (T::from_f64(2.0) * T::PI * (start * t + rate_of_change * t * t / T::from_f64(2.0))).sin()
+    }).collect()
+}
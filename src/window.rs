@@ -0,0 +1,217 @@
+use complex::Complex;
+use float::Float;
+
+/// Symmetric windows have equal first and last samples and suit FIR design. Periodic
+/// windows are the first `len` samples of a symmetric window of length `len + 1`, and
+/// are the ones to use for spectral analysis with overlapping frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    Symmetric,
+    Periodic
+}
+
+/// Position of sample `n` in `[0, 1]` over the window span.
+fn positions<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    let span = match (symmetry, len) {
+        (_, 0) | (_, 1) => 1,
+        (Symmetry::Symmetric, len) => len - 1,
+        (Symmetry::Periodic, len) => len
+    };
+    (0..len).map(|n| T::from_usize(n) / T::from_usize(span)).collect()
+}
+
+/// `shape` at each sample's position, or a lone unit sample for a window of length one.
+fn shaped<T: Float, F: Fn(T) -> T>(len: usize, symmetry: Symmetry, shape: F) -> Vec<T> {
+    if len == 1 {
+        return vec![T::one()];
+    }
+    positions::<T>(len, symmetry).into_iter().map(shape).collect()
+}
+
+fn cosine_sum<T: Float>(len: usize, coeffs: &[f64], symmetry: Symmetry) -> Vec<T> {
+    shaped(len, symmetry, |x: T| coeffs.iter().enumerate().fold(T::zero(), |acc, (k, &a)| {
+        let term = T::from_f64(a) * (T::from_f64(2.0) * T::PI * T::from_usize(k) * x).cos();
+        match k % 2 {
+            0 => acc + term,
+            _ => acc - term
+        }
+    }))
+}
+
+pub fn rectangular<T: Float>(len: usize) -> Vec<T> {
+    vec![T::one(); len]
+}
+
+pub fn hann<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    cosine_sum(len, &[0.5, 0.5], symmetry)
+}
+
+pub fn hamming<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    cosine_sum(len, &[0.54, 0.46], symmetry)
+}
+
+pub fn blackman<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    cosine_sum(len, &[0.42, 0.5, 0.08], symmetry)
+}
+
+/// Four-term Blackman-Harris, -92 dB sidelobes.
+pub fn blackman_harris<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    cosine_sum(len, &[0.35875, 0.48829, 0.14128, 0.01168], symmetry)
+}
+
+/// Four-term Nuttall window with continuous first derivative.
+pub fn nuttall<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    cosine_sum(len, &[0.355768, 0.487396, 0.144232, 0.012604], symmetry)
+}
+
+/// Flat-top window for amplitude measurements, with negligible scalloping loss.
+pub fn flat_top<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    cosine_sum(len, &[0.21557895, 0.41663158, 0.277263158, 0.083578947, 0.006947368], symmetry)
+}
+
+/// Heinzel's HFT116D flat-top scaled to a unit peak. Its -116 dB sidelobes suit high
+/// dynamic range measurements, at the cost of a wider main lobe than `flat_top`.
+pub fn hft116d<T: Float>(len: usize, symmetry: Symmetry) -> Vec<T> {
+    cosine_sum(len, &[0.192240453, 0.376317895, 0.284144942, 0.122407782, 0.023614606, 0.001274324], symmetry)
+}
+
+/// Tukey window: a flat top with cosine tapers over the fraction `alpha` of the
+/// length. `alpha = 0` is rectangular and `alpha = 1` is Hann.
+pub fn tukey<T: Float>(len: usize, alpha: T, symmetry: Symmetry) -> Vec<T> {
+    let half = T::from_f64(0.5);
+    shaped(len, symmetry, |x: T| {
+        let edge = x.min(T::one() - x);
+        match edge {
+            e if e >= alpha * half => T::one(),
+            e => half - half * (T::from_f64(2.0) * T::PI * e / alpha).cos()
+        }
+    })
+}
+
+pub fn bessel_i0<T: Float>(x: T) -> T {
+    let quarter = x * x / T::from_f64(4.0);
+    let (mut sum, mut term, mut k) = (T::one(), T::one(), 1);
+    while term > sum * T::EPSILON {
+        term = term * quarter / T::from_usize(k * k);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+pub fn kaiser<T: Float>(len: usize, beta: T, symmetry: Symmetry) -> Vec<T> {
+    let scale = bessel_i0(beta);
+    shaped(len, symmetry, |x: T| {
+        let r = T::from_f64(2.0) * x - T::one();
+        bessel_i0(beta * (T::one() - r * r).max(T::zero()).sqrt()) / scale
+    })
+}
+
+/// Gaussian window with standard deviation `sigma` as a fraction of half the span.
+pub fn gaussian<T: Float>(len: usize, sigma: T, symmetry: Symmetry) -> Vec<T> {
+    shaped(len, symmetry, |x: T| {
+        let r = (T::from_f64(2.0) * x - T::one()) / sigma;
+        (-r * r / T::from_f64(2.0)).exp()
+    })
+}
+
+/// Calibration figures of a window. A tone centred on a bin reads `coherent_gain`
+/// times its amplitude; one half a bin off reads a further `scalloping_loss` dB lower.
+/// `enbw` is the equivalent noise bandwidth in bins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowMetrics<T> {
+    pub coherent_gain: T,
+    pub enbw: T,
+    pub scalloping_loss: T
+}
+
+pub fn metrics<T: Float>(window: &[T]) -> WindowMetrics<T> {
+    let len = T::from_usize(window.len());
+    let sum: T = window.iter().cloned().sum();
+    let power: T = window.iter().map(|&w| w * w).sum();
+    let half_bin = window.iter().enumerate()
+        .fold(Complex::default(), |acc, (n, &w)| acc + Complex::from_polar(w, -T::PI * T::from_usize(n) / len));
+
+    WindowMetrics {
+        coherent_gain: sum / len,
+        enbw: len * power / (sum * sum),
+        scalloping_loss: -T::from_f64(20.0) * (half_bin.norm() / sum).log10()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Symmetry, blackman, blackman_harris, flat_top, gaussian, hamming, hann, hft116d, kaiser,
+        metrics, nuttall, rectangular, tukey };
+
+    fn assert_close(a: &[f64], b: &[f64], tolerance: f64) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < tolerance, "{} != {}", x, y);
+        }
+    }
+
+    fn all(len: usize, symmetry: Symmetry) -> Vec<Vec<f64>> {
+        vec![
+            hann(len, symmetry), hamming(len, symmetry), blackman(len, symmetry),
+            blackman_harris(len, symmetry), nuttall(len, symmetry), flat_top(len, symmetry), hft116d(len, symmetry),
+            tukey(len, 0.5, symmetry), kaiser(len, 8.6, symmetry), gaussian(len, 0.4, symmetry)
+        ]
+    }
+
+    #[test]
+    fn test_symmetry() {
+        for &len in &[7, 64] {
+            for (sym, (per, ext)) in all(len, Symmetry::Symmetric).iter()
+                .zip(all(len, Symmetry::Periodic).iter().zip(all(len + 1, Symmetry::Symmetric).iter())) {
+                let reversed: Vec<_> = sym.iter().rev().cloned().collect();
+                assert_close(sym, &reversed, 1e-12);
+                assert_close(per, &ext[..len], 1e-12);
+                assert!((sym[(len - 1) / 2].max(sym[len / 2]) - 1.0).abs() < 0.02);
+            }
+        }
+    }
+
+    #[test]
+    fn test_short_windows() {
+        for &symmetry in &[Symmetry::Symmetric, Symmetry::Periodic] {
+            assert!(all(0, symmetry).iter().all(|w| w.is_empty()));
+            assert!(all(1, symmetry).iter().all(|w| w == &[1.0]), "{:?}", all(1, symmetry));
+        }
+    }
+
+    #[test]
+    fn test_definitions() {
+        assert_close(&hann(5, Symmetry::Symmetric), &[0.0, 0.5, 1.0, 0.5, 0.0], 1e-12);
+        assert_close(&hann(4, Symmetry::Periodic), &[0.0, 0.5, 1.0, 0.5], 1e-12);
+        assert_close(&hamming(3, Symmetry::Symmetric), &[0.08, 1.0, 0.08], 1e-12);
+        assert_close(&blackman(3, Symmetry::Symmetric), &[0.0, 1.0, 0.0], 1e-12);
+        assert_close(&tukey(64, 0.0, Symmetry::Periodic), &rectangular(64), 1e-12);
+        assert_close(&tukey(64, 1.0, Symmetry::Symmetric), &hann(64, Symmetry::Symmetric), 1e-12);
+        assert_close(&kaiser(64, 0.0, Symmetry::Symmetric), &rectangular(64), 1e-12);
+        assert!((kaiser::<f64>(9, 5.0, Symmetry::Symmetric)[0] - 0.036_710_892).abs() < 1e-8);
+        assert!((gaussian::<f64>(9, 0.5, Symmetry::Symmetric)[0] - (-2.0f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_metrics() {
+        // (window, coherent gain, ENBW in bins, scalloping loss in dB)
+        let expected: Vec<(Vec<f64>, f64, f64, f64)> = vec![
+            (rectangular(1024), 1.0, 1.0, 3.92),
+            (hann(1024, Symmetry::Periodic), 0.5, 1.5, 1.42),
+            (hamming(1024, Symmetry::Periodic), 0.54, 1.363, 1.75),
+            (blackman(1024, Symmetry::Periodic), 0.42, 1.727, 1.10),
+            (blackman_harris(1024, Symmetry::Periodic), 0.359, 2.004, 0.83),
+            (nuttall(1024, Symmetry::Periodic), 0.356, 2.021, 0.81),
+            (flat_top(1024, Symmetry::Periodic), 0.216, 3.770, 0.01),
+            (hft116d(1024, Symmetry::Periodic), 0.192, 4.219, 0.01)
+        ];
+
+        for (window, gain, enbw, scalloping) in expected {
+            let m = metrics(&window);
+            assert!((m.coherent_gain - gain).abs() < 1e-3, "{:?}", m);
+            assert!((m.enbw - enbw).abs() < 2e-3, "{:?}", m);
+            assert!((m.scalloping_loss - scalloping).abs() < 0.01, "{:?}", m);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use complex::Complex;
+use float::Float;
+use pitch::parabolic_offset;
+use { fft_complex, ifft };
+use Wave;
+
+/// Circular correlation `ifft(conj(A) B)` of `a` and `b` zero padded so nothing wraps,
+/// with `weight` applied to each bin of the cross spectrum.
+fn correlate<T, F>(a: &[T], b: &[T], weight: F) -> (Vec<T>, usize)
+    where T: Float, F: Fn(Complex<T>) -> Complex<T> {
+    let len = (a.len() + b.len()).next_power_of_two();
+    let spectrum = |s: &[T]| {
+        let mut v: Vec<_> = s.iter().map(|&x| Complex::new(x, T::zero())).collect();
+        v.resize(len, Complex::default());
+        fft_complex(v)
+    };
+    let cross = spectrum(a).into_iter().zip(spectrum(b)).map(|(x, y)| weight(x.conj() * y)).collect();
+    (ifft(cross).into_iter().map(|c| c.re).collect(), len)
+}
+
+fn arrange<T: Float>(circular: &[T], len: usize, a_len: usize, b_len: usize) -> Vec<T> {
+    (0..a_len + b_len - 1).map(|i| circular[(i + len + 1 - a_len) % len]).collect()
+}
+
+/// Full cross-correlation `r(lag) = sum a[n] b[n + lag]` for lags `-(a.len() - 1)`
+/// through `b.len() - 1`, so index `a.len() - 1` is lag zero and a peak at a
+/// positive lag means `b` lags behind `a`.
+pub fn cross_correlation<T: Float>(a: &[T], b: &[T]) -> Vec<T> {
+    let (r, len) = correlate(a, b, |x| x);
+    arrange(&r, len, a.len(), b.len())
+}
+
+/// Biased autocorrelation `r(lag) = sum src[n] src[n + lag]` for lags `0..src.len()`.
+pub fn autocorrelation<T: Float>(src: &[T]) -> Vec<T> {
+    let (r, _) = correlate(src, src, |x| x);
+    r.into_iter().take(src.len()).collect()
+}
+
+/// Generalized cross-correlation with phase transform: every bin of the cross
+/// spectrum is normalized to unit magnitude, which turns a pure delay into a sharp
+/// peak regardless of the signals' spectra. Laid out like `cross_correlation`.
+pub fn gcc_phat<T: Float>(a: &[T], b: &[T]) -> Vec<T> {
+    let floor = T::from_f64(1e-20);
+    let (r, len) = correlate(a, b, |x| match x.norm() {
+        n if n > floor => x / n,
+        _ => Complex::default()
+    });
+    arrange(&r, len, a.len(), b.len())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weighting {
+    None,
+    Phat
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Delay<T> {
+    /// Lag of `b` behind `a` in samples, with parabolic sub-sample interpolation.
+    pub samples: T,
+    pub seconds: T,
+    /// Correlation value at the integer peak.
+    pub peak: T
+}
+
+/// Estimates how far the second signal lags behind the first from the highest
+/// correlation peak within `max_lag` samples either way.
+#[derive(Clone, Debug)]
+pub struct DelayEstimator {
+    pub weighting: Weighting,
+    pub max_lag: Option<usize>
+}
+
+impl Default for DelayEstimator {
+    fn default() -> Self {
+        DelayEstimator { weighting: Weighting::Phat, max_lag: None }
+    }
+}
+
+impl DelayEstimator {
+    pub fn estimate<T: Float>(&self, a: &[T], b: &[T], sample_rate: u32) -> Delay<T> {
+        assert!(!a.is_empty() && !b.is_empty(), "an empty signal has no delay to estimate");
+        let r = match self.weighting {
+            Weighting::None => cross_correlation(a, b),
+            Weighting::Phat => gcc_phat(a, b)
+        };
+        let zero = a.len() - 1;
+        let (lo, hi) = match self.max_lag {
+            Some(max) => (zero.saturating_sub(max), (zero + max).min(r.len() - 1)),
+            None => (0, r.len() - 1)
+        };
+
+        let best = (lo..hi + 1).fold(lo, |best, i| if r[i] > r[best] { i } else { best });
+        let offset = match best {
+            i if i > 0 && i + 1 < r.len() => parabolic_offset(r[i - 1], r[i], r[i + 1]),
+            _ => T::zero()
+        };
+        let samples = T::from_f64(best as f64 - zero as f64) + offset;
+        Delay { samples, seconds: samples / T::from_f64(sample_rate as f64), peak: r[best] }
+    }
+
+    pub fn estimate_waves(&self, a: &Wave, b: &Wave) -> Delay<f32> {
+        assert_eq!(a.format.sample_rate, b.format.sample_rate, "sample rates differ");
+        self.estimate(&a.data, &b.data, a.format.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ DelayEstimator, Weighting, autocorrelation, cross_correlation, gcc_phat };
+    use complex::Complex;
+    use synth::noise;
+    use { fft_complex, ifft, read_wave_mono16 };
+
+    const LEN: usize = 8192;
+
+    fn pulse_train() -> (Vec<f64>, u32) {
+        let wave = read_wave_mono16("examples/resources/pulse_train.wav");
+        (wave.data.iter().map(|&x| x as f64).collect(), wave.format.sample_rate)
+    }
+
+    /// `LEN` samples starting at `start`, delayed by `delay` samples through a linear
+    /// phase shift.
+    fn delayed(src: &[f64], start: usize, delay: f64) -> Vec<f64> {
+        let n = 4 * LEN;
+        let padded = src[start - LEN..start + 2 * LEN].iter().map(|&x| Complex::new(x, 0.0))
+            .chain((0..LEN).map(|_| Complex::default())).collect();
+        let shifted = fft_complex(padded).into_iter().enumerate().map(|(k, c)| {
+            let freq = if k > n / 2 { k as f64 - n as f64 } else if k == n / 2 { 0.0 } else { k as f64 };
+            c * Complex::from_polar(1.0, -2.0 * ::std::f64::consts::PI * freq * delay / n as f64)
+        }).collect();
+        ifft(shifted).into_iter().skip(LEN).take(LEN).map(|c| c.re).collect()
+    }
+
+    #[test]
+    fn test_definitions() {
+        let (a, b) = ([1.0, 2.0, 3.0], [0.0, 1.0, 0.5, -1.0]);
+        let expected: Vec<f64> = (-2..4).map(|lag: isize| (0..3).filter_map(|n| {
+            let m = n as isize + lag;
+            match m {
+                m if m >= 0 && (m as usize) < b.len() => Some(a[n] * b[m as usize]),
+                _ => None
+            }
+        }).sum()).collect();
+
+        for (x, y) in cross_correlation(&a, &b).iter().zip(expected.iter()) {
+            assert!((x - y).abs() < 1e-12, "{} != {}", x, y);
+        }
+        assert_eq!(cross_correlation(&a, &b).len(), 6);
+        assert_eq!(autocorrelation(&a).iter().map(|x| x.round()).collect::<Vec<_>>(), vec![14.0, 8.0, 3.0]);
+
+        let phat = gcc_phat(&a, &[0.0, 0.0, 1.0, 2.0, 3.0]);
+        let peak = (0..phat.len()).fold(0, |best, i| if phat[i] > phat[best] { i } else { best });
+        assert_eq!(peak as isize - 2, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty signal")]
+    fn test_empty_signal() {
+        DelayEstimator::default().estimate::<f64>(&[], &[1.0, 2.0], 8000);
+    }
+
+    #[test]
+    fn test_pulse_train_delays() {
+        let (src, rate) = pulse_train();
+        let a = &src[20000..20000 + LEN];
+
+        for &weighting in &[Weighting::None, Weighting::Phat] {
+            let estimator = DelayEstimator { weighting, max_lag: Some(40) };
+            for &delay in &[-31isize, -7, 0, 12, 37] {
+                let start = (20000 - delay) as usize;
+                let res = estimator.estimate(a, &src[start..start + LEN], rate);
+                assert!((res.samples - delay as f64).abs() < 1e-2, "{:?}: {} != {}", weighting, res.samples, delay);
+                assert!((res.seconds - res.samples / rate as f64).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sub_sample_delays() {
+        let (src, rate) = pulse_train();
+        let a = delayed(&src, 20000, 0.0);
+
+        for &weighting in &[Weighting::None, Weighting::Phat] {
+            let estimator = DelayEstimator { weighting, max_lag: Some(40) };
+            for &delay in &[-3.25, 0.5, 10.4, 21.75] {
+                let res = estimator.estimate(&a, &delayed(&src, 20000, delay), rate);
+                assert!((res.samples - delay).abs() < 0.15, "{:?}: {} != {}", weighting, res.samples, delay);
+            }
+        }
+    }
+
+    #[test]
+    fn test_phat_with_noise() {
+        let (src, rate) = pulse_train();
+        let a: Vec<_> = src[20000..20000 + LEN].iter().zip(noise(0.05, LEN, 1)).map(|(x, n)| x + n).collect();
+        let b: Vec<_> = src[19975..19975 + LEN].iter().zip(noise(0.05, LEN, 2)).map(|(x, n)| x + n).collect();
+
+        let res = DelayEstimator { max_lag: Some(40), ..DelayEstimator::default() }.estimate(&a, &b, rate);
+        assert!((res.samples - 25.0).abs() < 0.2, "{}", res.samples);
+    }
+}